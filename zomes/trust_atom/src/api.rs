@@ -0,0 +1,52 @@
+//! Rust API for embedding trust atoms in a host coordinator zome, rather than installing
+//! `trust_atom` as a zome of its own.  Depend on this crate without its default `externs`
+//! feature, so that none of its `#[hdk_extern]` zome functions are exported:
+//!
+//! ```toml
+//! trust_atom = { path = "…", default-features = false }
+//! ```
+//!
+//! and call eg `trust_atom::api::create(…)` from the host's own zome functions.
+//!
+//! The core functions (`create`, `create_many`, `delete`, `query`, `query_mine`) take the link
+//! type to store trust atoms under, so a host can declare its own in its integrity zome:
+//!
+//! ```ignore
+//! #[hdk_link_types]
+//! pub enum LinkTypes {
+//!   Rating,
+//! }
+//!
+//! trust_atom::api::create(
+//!   LinkTypes::Rating,
+//!   target,
+//!   Some("sushi".into()),
+//!   Some(".8".into()),
+//!   None, // confidence
+//!   None, // extra
+//! )?;
+//! ```
+//!
+//! `extra` fields are stored as `trust_atom_integrity` `Extra` entries, and the other functions
+//! (rollups, similarity, targets, exports…) use `trust_atom_integrity`'s own entry and link
+//! types, so hosts using those must also include the `trust_atom_integrity` zome in their DNA.
+//!
+//! Settings such as the link tag format are read from the host DNA's properties; see
+//! `trust_atom_types::TrustAtomProperties`.
+
+pub use crate::bundle::{export_my_trust_atoms, verify_trust_atom_bundle};
+pub use crate::credential::{issue_trust_atom_credential, verify_trust_atom_credential};
+pub use crate::external_target::{
+  create_external_target, get_external_target_uri, query_external_target,
+};
+pub use crate::graph_export::{crawl_graph, export_graph};
+pub use crate::migrate::migrate_mine;
+pub use crate::properties::trust_atom_properties;
+pub use crate::recommend::recommend;
+pub use crate::rollup::{cache_rollup, get_cached_rollup, rollup, summarize_target};
+pub use crate::similarity::{agent_similarity, similar_agents};
+pub use crate::targets::{create_string_target, lookup_string_target};
+pub use crate::trust_atom::{
+  calc_extra_hash, create, create_many, delete, get_extra, query, query_mine,
+  query_with_decode_errors,
+};
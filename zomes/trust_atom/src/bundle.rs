@@ -0,0 +1,78 @@
+#![allow(clippy::module_name_repetitions)]
+
+use hdk::prelude::*;
+use trust_atom_codec::{decode, Direction};
+use trust_atom_integrity::headers::build_forward_header;
+use trust_atom_integrity::LinkTypes;
+use trust_atom_types::bundle::{TrustAtomBundle, TrustAtomBundlePayload};
+use trust_atom_types::{TimestampedTrustAtom, VerifiedTrustAtomBundle};
+
+use crate::trust_atom::{convert_link_to_trust_atom, extra_hash_from_link_tag, get_extra};
+
+/// All my trust atoms, with their extra fields and timestamps, signed by my agent key
+pub fn export_my_trust_atoms() -> ExternResult<TrustAtomBundle> {
+  let agent = agent_info()?.agent_initial_pubkey;
+  let trust_atoms = my_timestamped_trust_atoms(&agent)?;
+
+  let payload = TrustAtomBundlePayload::new(&agent, sys_time()?, &trust_atoms)
+    .to_json_string()
+    .map_err(|error| wasm_error!("{}", error))?;
+  let signature = sign_raw(agent, payload.clone().into_bytes())?;
+
+  Ok(TrustAtomBundle { payload, signature })
+}
+
+/// Checks the bundle signature against the bundle's agent; needs no network access
+pub fn verify_trust_atom_bundle(bundle: &TrustAtomBundle) -> ExternResult<VerifiedTrustAtomBundle> {
+  let payload = TrustAtomBundlePayload::from_json_str(&bundle.payload)
+    .map_err(|error| wasm_error!("{}", error))?;
+  let agent = payload.signer().map_err(|error| wasm_error!("{}", error))?;
+
+  if !verify_signature_raw(
+    agent.clone(),
+    bundle.signature.clone(),
+    bundle.payload.clone().into_bytes(),
+  )? {
+    return Err(wasm_error!(
+      "TrustAtom bundle signature is not valid for agent {}",
+      agent
+    ));
+  }
+
+  Ok(VerifiedTrustAtomBundle {
+    agent,
+    exported_at: Timestamp::from_micros(payload.exported_at),
+    trust_atoms: payload
+      .trust_atoms()
+      .map_err(|error| wasm_error!("{}", error))?,
+  })
+}
+
+fn my_timestamped_trust_atoms(agent: &AgentPubKey) -> ExternResult<Vec<TimestampedTrustAtom>> {
+  let agent_address = AnyLinkableHash::from(agent.clone());
+  let links = get_links(
+    agent_address.clone(),
+    LinkTypes::TrustAtom,
+    Some(LinkTag::new(build_forward_header())),
+  )?;
+
+  // Like `query`, skip links which aren't trust atoms this zome can read
+  links
+    .into_iter()
+    .filter(|link| decode(&link.tag.0).is_ok())
+    .map(|link| {
+      let timestamp = link.timestamp;
+      let extra = match extra_hash_from_link_tag(&link.tag)? {
+        Some(extra_hash) => Some(get_extra(&extra_hash)?.fields),
+        None => None,
+      };
+      let mut trust_atom =
+        convert_link_to_trust_atom(link, Direction::Forward, agent_address.clone())?;
+      trust_atom.extra = extra;
+      Ok(TimestampedTrustAtom {
+        trust_atom,
+        timestamp,
+      })
+    })
+    .collect()
+}
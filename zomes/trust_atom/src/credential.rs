@@ -0,0 +1,54 @@
+#![allow(clippy::module_name_repetitions)]
+
+use hdk::prelude::*;
+use trust_atom_types::credential::TrustAtomCredential;
+use trust_atom_types::TrustAtom;
+
+/// W3C Verifiable Credential (JSON) for one of my trust atoms, with a proof signed by my agent key
+pub fn issue_trust_atom_credential(trust_atom: &TrustAtom) -> ExternResult<String> {
+  let agent = agent_info()?.agent_initial_pubkey;
+  if trust_atom.source_hash != AnyLinkableHash::from(agent.clone()) {
+    return Err(wasm_error!(
+      "Only the source agent of a trust atom can issue a credential for it"
+    ));
+  }
+
+  let credential = TrustAtomCredential::unsigned(trust_atom);
+  let signing_input = credential
+    .signing_input()
+    .map_err(|error| wasm_error!("{}", error))?;
+  let signature = sign_raw(agent, signing_input)?;
+
+  credential
+    .with_signature(&signature.0)
+    .to_json_string()
+    .map_err(|error| wasm_error!("{}", error))
+}
+
+/// Checks the credential proof against its issuer, and returns the trust atom it describes
+pub fn verify_trust_atom_credential(credential: &str) -> ExternResult<TrustAtom> {
+  let credential =
+    TrustAtomCredential::from_json_str(credential).map_err(|error| wasm_error!("{}", error))?;
+  let agent = credential
+    .issuer_agent()
+    .map_err(|error| wasm_error!("{}", error))?;
+  let signature: [u8; 64] = credential
+    .signature()
+    .map_err(|error| wasm_error!("{}", error))?
+    .try_into()
+    .map_err(|_| wasm_error!("Credential signature must be 64 bytes"))?;
+  let signing_input = credential
+    .signing_input()
+    .map_err(|error| wasm_error!("{}", error))?;
+
+  if !verify_signature_raw(agent.clone(), Signature(signature), signing_input)? {
+    return Err(wasm_error!(
+      "Credential proof is not valid for issuer {}",
+      agent
+    ));
+  }
+
+  credential
+    .to_trust_atom()
+    .map_err(|error| wasm_error!("{}", error))
+}
@@ -0,0 +1,77 @@
+#![allow(clippy::module_name_repetitions)]
+
+use hdk::prelude::*;
+use trust_atom_integrity::entries::{EntryTypes, ExternalTarget};
+use trust_atom_integrity::LinkTypes;
+use trust_atom_types::uri::{external_hash_for_uri, is_absolute_uri};
+use trust_atom_types::{QueryExternalTargetInput, TrustAtom};
+
+use crate::trust_atom;
+
+/// `ExternalHash` of a URL, IPFS CID or other URI, to use as a trust atom target.
+/// Stores the URI, so that `get_external_target_uri` can resolve the hash; idempotent.
+pub fn create_external_target(uri: &str) -> ExternResult<ExternalHash> {
+  let external_hash = external_target_hash(uri)?;
+
+  if get_external_target_uri(&external_hash)?.is_none() {
+    let external_target = ExternalTarget {
+      uri: uri.to_string(),
+    };
+    create_entry(EntryTypes::ExternalTarget(external_target.clone()))?;
+    create_link(
+      external_hash.clone(),
+      hash_entry(external_target)?,
+      LinkTypes::ExternalTarget,
+      (),
+    )?;
+  }
+
+  Ok(external_hash)
+}
+
+/// The URI an `ExternalHash` was created from, if anyone has registered it.  Links to any other
+/// URI are skipped, though validation should have rejected them.
+pub fn get_external_target_uri(external_hash: &ExternalHash) -> ExternResult<Option<String>> {
+  for link in external_target_links(external_hash)? {
+    if let AnyLinkablePrimitive::Entry(entry_hash) = link.target.into_primitive() {
+      if let Some(record) = get(entry_hash, GetOptions::default())? {
+        let external_target: Option<ExternalTarget> = record
+          .entry()
+          .to_app_option()
+          .map_err(|e| wasm_error!(WasmErrorInner::Serialize(e)))?;
+        if let Some(external_target) = external_target {
+          if external_hash_for_uri(&external_target.uri) == *external_hash {
+            return Ok(Some(external_target.uri));
+          }
+        }
+      }
+    }
+  }
+  Ok(None)
+}
+
+/// Trust atoms about an external resource, without the caller computing its hash
+pub fn query_external_target(input: &QueryExternalTargetInput) -> ExternResult<Vec<TrustAtom>> {
+  trust_atom::query(
+    LinkTypes::TrustAtom,
+    None,
+    Some(AnyLinkableHash::from(external_target_hash(&input.uri)?)),
+    input.content_full.clone(),
+    input.content_starts_with.clone(),
+    input.value_starts_with.clone(),
+  )
+}
+
+fn external_target_hash(uri: &str) -> ExternResult<ExternalHash> {
+  if uri.starts_with("hc:") || !is_absolute_uri(uri) {
+    return Err(wasm_error!(
+      "External target must be an absolute, non-Holochain URI, but got: `{}`",
+      uri
+    ));
+  }
+  Ok(external_hash_for_uri(uri))
+}
+
+fn external_target_links(external_hash: &ExternalHash) -> ExternResult<Vec<Link>> {
+  get_links(external_hash.clone(), LinkTypes::ExternalTarget, None)
+}
@@ -0,0 +1,237 @@
+use hdk::prelude::*;
+#[cfg(feature = "test-helpers")]
+use trust_atom_integrity::entries::Example;
+use trust_atom_integrity::entries::Extra;
+use trust_atom_integrity::LinkTypes;
+use trust_atom_types::bundle::TrustAtomBundle;
+use trust_atom_types::{
+  AgentSimilarity, AgentSimilarityInput, CacheRollupInput, CachedRollupOutput,
+  CreateTrustAtomResult, DeleteReport, ExportGraphInput, GetCachedRollupInput,
+  MigrateTrustAtomsInput, MigrationReport, QueryExternalTargetInput, QueryInput, QueryMineInput,
+  QueryOutput, RecommendInput, Recommendation, Rollup, RollupInput, SimilarAgentsInput,
+  StringTargetInput, SummarizeTargetInput, TargetSummary, TrustAtom, TrustAtomInput,
+  VerifiedTrustAtomBundle,
+};
+
+#[cfg(feature = "test-helpers")]
+use crate::test_helpers;
+use crate::{
+  bundle, credential, external_target, graph_export, migrate, recommend, rollup, similarity,
+  targets, trust_atom,
+};
+
+// ZOME API FUNCTIONS
+
+#[hdk_extern]
+pub fn create_trust_atom(input: TrustAtomInput) -> ExternResult<TrustAtom> {
+  let trust_atom = trust_atom::create(
+    LinkTypes::TrustAtom,
+    input.target,
+    input.content,
+    input.value,
+    input.confidence,
+    input.extra,
+  )?;
+  Ok(trust_atom)
+}
+
+/// Batch version of `create_trust_atom`, with one result per input, in order
+#[hdk_extern]
+#[allow(clippy::needless_pass_by_value)]
+pub fn create_trust_atoms(inputs: Vec<TrustAtomInput>) -> ExternResult<Vec<CreateTrustAtomResult>> {
+  trust_atom::create_many(LinkTypes::TrustAtom, &inputs)
+}
+
+#[hdk_extern]
+pub fn delete_trust_atoms(target: AnyLinkableHash) -> ExternResult<DeleteReport> {
+  trust_atom::delete(LinkTypes::TrustAtom, target)
+}
+
+#[hdk_extern]
+#[allow(clippy::needless_pass_by_value)]
+pub fn migrate_my_trust_atoms(input: MigrateTrustAtomsInput) -> ExternResult<MigrationReport> {
+  migrate::migrate_mine(LinkTypes::TrustAtom, &input)
+}
+
+#[hdk_extern]
+#[allow(clippy::needless_pass_by_value)]
+pub fn get_extra(entry_hash: EntryHash) -> ExternResult<Extra> {
+  let extra = trust_atom::get_extra(&entry_hash)?;
+  Ok(extra)
+}
+
+#[hdk_extern]
+pub fn calc_extra_hash(input: Extra) -> ExternResult<EntryHash> {
+  let hash = trust_atom::calc_extra_hash(input)?;
+  Ok(hash)
+}
+
+#[hdk_extern]
+pub fn query(input: QueryInput) -> ExternResult<QueryOutput> {
+  trust_atom::query_with_decode_errors(
+    LinkTypes::TrustAtom,
+    input.source,
+    input.target,
+    input.content_full,
+    input.content_starts_with,
+    input.value_starts_with,
+    input.strict.unwrap_or(false),
+  )
+}
+
+#[hdk_extern]
+pub fn query_mine(input: QueryMineInput) -> ExternResult<QueryOutput> {
+  let agent_address = AnyLinkableHash::from(agent_info()?.agent_initial_pubkey);
+  trust_atom::query_with_decode_errors(
+    LinkTypes::TrustAtom,
+    Some(agent_address),
+    input.target,
+    input.content_full,
+    input.content_starts_with,
+    input.value_starts_with,
+    input.strict.unwrap_or(false),
+  )
+}
+
+#[hdk_extern]
+#[allow(clippy::needless_pass_by_value)]
+pub fn summarize_target(input: SummarizeTargetInput) -> ExternResult<TargetSummary> {
+  rollup::summarize_target(&input)
+}
+
+#[hdk_extern]
+#[allow(clippy::needless_pass_by_value)]
+pub fn rollup(input: RollupInput) -> ExternResult<Rollup> {
+  rollup::rollup(&input)
+}
+
+#[hdk_extern]
+#[allow(clippy::needless_pass_by_value)]
+pub fn cache_rollup(input: CacheRollupInput) -> ExternResult<CachedRollupOutput> {
+  rollup::cache_rollup(&input)
+}
+
+#[hdk_extern]
+#[allow(clippy::needless_pass_by_value)]
+pub fn get_cached_rollup(input: GetCachedRollupInput) -> ExternResult<CachedRollupOutput> {
+  rollup::get_cached_rollup(&input)
+}
+
+#[hdk_extern]
+#[allow(clippy::needless_pass_by_value)]
+pub fn agent_similarity(input: AgentSimilarityInput) -> ExternResult<AgentSimilarity> {
+  similarity::agent_similarity(&input)
+}
+
+#[hdk_extern]
+#[allow(clippy::needless_pass_by_value)]
+pub fn similar_agents(input: SimilarAgentsInput) -> ExternResult<Vec<AgentSimilarity>> {
+  similarity::similar_agents(&input)
+}
+
+#[hdk_extern]
+#[allow(clippy::needless_pass_by_value)]
+pub fn recommend(input: RecommendInput) -> ExternResult<Vec<Recommendation>> {
+  recommend::recommend(&input)
+}
+
+#[hdk_extern]
+#[allow(clippy::needless_pass_by_value)]
+pub fn export_graph(input: ExportGraphInput) -> ExternResult<String> {
+  graph_export::export_graph(&input)
+}
+
+#[hdk_extern]
+pub fn export_my_trust_atoms(_: ()) -> ExternResult<TrustAtomBundle> {
+  bundle::export_my_trust_atoms()
+}
+
+#[hdk_extern]
+#[allow(clippy::needless_pass_by_value)]
+pub fn verify_trust_atom_bundle(bundle: TrustAtomBundle) -> ExternResult<VerifiedTrustAtomBundle> {
+  bundle::verify_trust_atom_bundle(&bundle)
+}
+
+#[hdk_extern]
+#[allow(clippy::needless_pass_by_value)]
+pub fn issue_trust_atom_credential(trust_atom: TrustAtom) -> ExternResult<String> {
+  credential::issue_trust_atom_credential(&trust_atom)
+}
+
+#[hdk_extern]
+#[allow(clippy::needless_pass_by_value)]
+pub fn verify_trust_atom_credential(credential: String) -> ExternResult<TrustAtom> {
+  credential::verify_trust_atom_credential(&credential)
+}
+
+#[hdk_extern]
+#[allow(clippy::needless_pass_by_value)]
+pub fn create_external_target(uri: String) -> ExternResult<ExternalHash> {
+  external_target::create_external_target(&uri)
+}
+
+#[hdk_extern]
+#[allow(clippy::needless_pass_by_value)]
+pub fn get_external_target_uri(external_hash: ExternalHash) -> ExternResult<Option<String>> {
+  external_target::get_external_target_uri(&external_hash)
+}
+
+#[hdk_extern]
+#[allow(clippy::needless_pass_by_value)]
+pub fn query_external_target(input: QueryExternalTargetInput) -> ExternResult<Vec<TrustAtom>> {
+  external_target::query_external_target(&input)
+}
+
+#[hdk_extern]
+#[allow(clippy::needless_pass_by_value)]
+pub fn create_string_target(input: StringTargetInput) -> ExternResult<EntryHash> {
+  targets::create_string_target(&input)
+}
+
+#[hdk_extern]
+#[allow(clippy::needless_pass_by_value)]
+pub fn lookup_string_target(input: StringTargetInput) -> ExternResult<Option<EntryHash>> {
+  targets::lookup_string_target(&input)
+}
+
+// TEST HELPERS (only with the `test-helpers` feature)
+
+#[cfg(feature = "test-helpers")]
+#[hdk_extern]
+pub fn create_test_entry(input: Example) -> ExternResult<ActionHash> {
+  test_helpers::create_test_entry(input)
+}
+
+#[cfg(feature = "test-helpers")]
+#[hdk_extern]
+pub fn test_get_entry_by_action(input: ActionHash) -> ExternResult<Example> {
+  test_helpers::get_entry_by_action(input)
+}
+
+#[cfg(feature = "test-helpers")]
+#[hdk_extern]
+pub fn test_helper_list_links(
+  (base, link_tag_text): (AnyLinkableHash, Option<String>),
+) -> ExternResult<Vec<Link>> {
+  test_helpers::list_links(base, link_tag_text)
+}
+
+#[cfg(feature = "test-helpers")]
+#[hdk_extern]
+pub fn test_helper_list_links_for_base(base: AnyLinkableHash) -> ExternResult<Vec<Link>> {
+  test_helpers::list_links_for_base(base)
+}
+
+#[cfg(feature = "test-helpers")]
+#[hdk_extern]
+pub fn test_helper_create_v1_trust_atom(input: TrustAtomInput) -> ExternResult<()> {
+  test_helpers::create_v1_trust_atom(input)
+}
+
+#[cfg(feature = "test-helpers")]
+#[hdk_extern]
+pub fn test_helper_create_external_target_link(
+  (external_hash, uri): (ExternalHash, String),
+) -> ExternResult<()> {
+  test_helpers::create_external_target_link(external_hash, uri)
+}
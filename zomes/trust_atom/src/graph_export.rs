@@ -0,0 +1,75 @@
+#![allow(clippy::module_name_repetitions)]
+
+use hdk::prelude::*;
+use std::collections::HashSet;
+use trust_atom_codec::{decode, Direction};
+use trust_atom_integrity::headers::build_forward_header;
+use trust_atom_integrity::LinkTypes;
+use trust_atom_types::graph_export::{export, TrustGraph, TrustGraphEdge};
+use trust_atom_types::ExportGraphInput;
+
+use crate::rollup::seed_or_me;
+use crate::trust_atom::convert_link_to_trust_atom;
+
+const MAX_EXPORT_DEPTH: u8 = 2;
+
+pub fn export_graph(input: &ExportGraphInput) -> ExternResult<String> {
+  let graph = crawl_graph(input.seed.clone(), input.depth)?;
+  Ok(export(&graph, input.format))
+}
+
+/// All trust atoms of `seed`, plus those of their targets, and so on `depth` hops out
+pub fn crawl_graph(seed: Option<AnyLinkableHash>, depth: u8) -> ExternResult<TrustGraph> {
+  if depth > MAX_EXPORT_DEPTH {
+    return Err(wasm_error!(
+      "`depth` must be in the range 0..{}, but got: `{}`",
+      MAX_EXPORT_DEPTH,
+      depth
+    ));
+  }
+
+  let seed = seed_or_me(seed)?;
+  let mut visited: HashSet<AnyLinkableHash> = HashSet::from([seed.clone()]);
+  let mut frontier: Vec<AnyLinkableHash> = vec![seed];
+  let mut edges: Vec<TrustGraphEdge> = vec![];
+
+  for _ in 0..=depth {
+    let mut next_frontier: Vec<AnyLinkableHash> = vec![];
+    for source in frontier {
+      for edge in forward_edges(&source)? {
+        if visited.insert(edge.target.clone()) {
+          next_frontier.push(edge.target.clone());
+        }
+        edges.push(edge);
+      }
+    }
+    frontier = next_frontier;
+  }
+
+  Ok(TrustGraph::from_edges(edges))
+}
+
+fn forward_edges(source: &AnyLinkableHash) -> ExternResult<Vec<TrustGraphEdge>> {
+  let links = get_links(
+    source.clone(),
+    LinkTypes::TrustAtom,
+    Some(LinkTag::new(build_forward_header())),
+  )?;
+
+  // Like `query`, skip links which aren't trust atoms this zome can read
+  links
+    .into_iter()
+    .filter(|link| decode(&link.tag.0).is_ok())
+    .map(|link| {
+      let timestamp = link.timestamp;
+      let trust_atom = convert_link_to_trust_atom(link, Direction::Forward, source.clone())?;
+      Ok(TrustGraphEdge {
+        source: trust_atom.source_hash,
+        target: trust_atom.target_hash,
+        content: trust_atom.content,
+        value: trust_atom.value,
+        timestamp,
+      })
+    })
+    .collect()
+}
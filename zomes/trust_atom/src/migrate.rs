@@ -0,0 +1,114 @@
+#![allow(clippy::module_name_repetitions)]
+
+use hdk::prelude::*;
+use trust_atom_codec::{decode, encode, header, Direction, TagVersion, TrustAtomTag};
+use trust_atom_integrity::headers::build_forward_header;
+use trust_atom_types::{MigrateTrustAtomsInput, MigrationReport};
+
+use crate::properties::tag_version;
+use crate::trust_atom::{create_bucket, create_link_pair};
+
+/// Rewrites up to `batch_size` of my trust atoms which are not in the current tag format (see
+/// `TrustAtomProperties`): each gets a new pair of links with the same content, value and extra,
+/// and a fresh bucket, and its old pair is deleted.  Migrated atoms are then in the current
+/// format, so the next call resumes where this one stopped.  An atom which can't be migrated (eg
+/// content over the current size limits) is left as it is and reported in `failed`, without
+/// counting towards `batch_size`, so it doesn't hold up the atoms after it.
+pub fn migrate_mine<L>(
+  link_type: L,
+  input: &MigrateTrustAtomsInput,
+) -> ExternResult<MigrationReport>
+where
+  L: LinkTypeFilterExt + Clone,
+  ScopedLinkType: TryFrom<L, Error = WasmError>,
+{
+  let version = tag_version()?;
+  let agent_address = AnyLinkableHash::from(agent_info()?.agent_initial_pubkey);
+  let links = get_links(
+    agent_address.clone(),
+    link_type.clone(),
+    Some(LinkTag::new(build_forward_header())),
+  )?;
+
+  let mut current = 0;
+  let mut undecodable = 0;
+  let mut outdated = vec![];
+  for link in links {
+    match decode(&link.tag.0) {
+      Ok(tag) if tag.version == version => current += 1,
+      Ok(tag) => outdated.push((link, tag)),
+      Err(_) => undecodable += 1,
+    }
+  }
+
+  let mut migrated = 0;
+  let mut failed = vec![];
+  let mut attempted = 0;
+  for (link, tag) in &outdated {
+    if migrated == input.batch_size {
+      break;
+    }
+    attempted += 1;
+    let migrated_tag = TrustAtomTag {
+      version,
+      bucket: Some(create_bucket()?),
+      ..tag.clone()
+    };
+    let result = if input.dry_run {
+      encode(&migrated_tag)
+        .map(|_| ())
+        .map_err(|error| wasm_error!("{}", error))
+    } else {
+      migrate_link(link_type.clone(), &agent_address, link, &migrated_tag)
+    };
+    match result {
+      Ok(()) => migrated += 1,
+      Err(_) => failed.push(link.create_link_hash.clone()),
+    }
+  }
+
+  Ok(MigrationReport {
+    dry_run: input.dry_run,
+    migrated,
+    remaining: outdated.len() - attempted,
+    current,
+    undecodable,
+    failed,
+  })
+}
+
+fn migrate_link<L>(
+  link_type: L,
+  agent_address: &AnyLinkableHash,
+  forward_link: &Link,
+  migrated_tag: &TrustAtomTag,
+) -> ExternResult<()>
+where
+  L: LinkTypeFilterExt + Clone,
+  ScopedLinkType: TryFrom<L, Error = WasmError>,
+{
+  // Checked before anything is written, so an atom which can't be migrated is left whole
+  encode(migrated_tag).map_err(|error| wasm_error!("{}", error))?;
+
+  // The reverse link's tag differs from the forward link's only in its arrow
+  let reverse_tag_bytes = [
+    header(Direction::Reverse),
+    forward_link.tag.0[header(Direction::Forward).len()..].to_vec(),
+  ]
+  .concat();
+  let reverse_links = get_links(
+    forward_link.target.clone(),
+    link_type.clone(),
+    Some(LinkTag::new(reverse_tag_bytes.clone())),
+  )?;
+
+  create_link_pair(link_type, agent_address, &forward_link.target, migrated_tag)?;
+
+  delete_link(forward_link.create_link_hash.clone())?;
+  for reverse_link in reverse_links {
+    if reverse_link.target == *agent_address && reverse_link.tag.0 == reverse_tag_bytes {
+      delete_link(reverse_link.create_link_hash)?;
+    }
+  }
+  Ok(())
+}
@@ -0,0 +1,30 @@
+#![allow(clippy::module_name_repetitions)]
+
+use hdk::prelude::*;
+use trust_atom_codec::TagVersion;
+use trust_atom_types::{TagFormat, TrustAtomProperties};
+
+/// Defaults if the DNA has no properties
+pub fn trust_atom_properties() -> ExternResult<TrustAtomProperties> {
+  let properties: Option<TrustAtomProperties> = ExternIO(dna_info()?.properties.bytes().clone())
+    .decode()
+    .map_err(|e| wasm_error!(WasmErrorInner::Serialize(e)))?;
+  Ok(properties.unwrap_or_default())
+}
+
+/// Tag version to create new trust atoms with
+pub(crate) fn tag_version() -> ExternResult<TagVersion> {
+  Ok(match trust_atom_properties()?.tag_format {
+    TagFormat::Text => TagVersion::LATEST,
+    TagFormat::Compact => TagVersion::Compact,
+  })
+}
+
+/// `content` as stored in link tags and searched for, per the DNA's `content_normalization`
+pub(crate) fn normalize_content(content: &str) -> ExternResult<String> {
+  Ok(
+    trust_atom_properties()?
+      .content_normalization
+      .normalize(content),
+  )
+}
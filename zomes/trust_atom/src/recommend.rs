@@ -0,0 +1,137 @@
+#![allow(clippy::module_name_repetitions)]
+
+use hdk::prelude::*;
+use rust_decimal::prelude::*;
+use std::collections::{HashMap, HashSet};
+use trust_atom_integrity::LinkTypes;
+use trust_atom_types::{
+  RecommendInput, RecommendVia, Recommendation, SimilarAgentsInput, SimilarityMetric, TrustAtom,
+};
+
+use crate::properties::normalize_content;
+use crate::rollup::{confidence_weight, parse_value, seed_or_me, trusted_raters};
+use crate::similarity::similar_agents;
+use crate::trust_atom;
+
+/// Number of similar agents consulted when recommending via `RecommendVia::SimilarAgents`
+const SIMILAR_AGENTS_LIMIT: usize = 20;
+
+#[derive(Debug, Clone, Default)]
+struct Candidate {
+  weighted_sum: Decimal,
+  weight_total: Decimal,
+  provenance: Vec<TrustAtom>,
+}
+
+/// Targets rated positively on `content` by agents I trust (or agents similar to me),
+/// which I have not yet rated on `content`, best first.  Each target's score is the mean of the
+/// ratings it got from those agents, weighted by the agent's weight and the rating's confidence;
+/// agents (or non-agent targets I rated) with no rating for it don't count.
+pub fn recommend(input: &RecommendInput) -> ExternResult<Vec<Recommendation>> {
+  let me = seed_or_me(None)?;
+  let content = normalize_content(&input.content)?;
+  let my_atoms = trust_atom::query(
+    LinkTypes::TrustAtom,
+    Some(me.clone()),
+    None,
+    None,
+    None,
+    None,
+  )?;
+
+  let already_rated: HashSet<AnyLinkableHash> = my_atoms
+    .iter()
+    .filter(|atom| atom.content.as_deref() == Some(content.as_str()))
+    .map(|atom| atom.target_hash.clone())
+    .collect();
+
+  let recommenders = recommenders(
+    &me,
+    &my_atoms,
+    input.via.unwrap_or(RecommendVia::TrustedAgents),
+  )?;
+  let mut candidates: HashMap<AnyLinkableHash, Candidate> = HashMap::new();
+  for (recommender, weight) in recommenders {
+    let atoms = trust_atom::query(
+      LinkTypes::TrustAtom,
+      Some(recommender),
+      None,
+      Some(content.clone()),
+      None,
+      None,
+    )?;
+    for atom in atoms {
+      if already_rated.contains(&atom.target_hash) || atom.target_hash == me {
+        continue;
+      }
+      if let Some(value) = parse_value(&atom).filter(|value| *value > Decimal::ZERO) {
+        let weight = weight * confidence_weight(&atom);
+        let candidate = candidates.entry(atom.target_hash.clone()).or_default();
+        candidate.weighted_sum += value * weight;
+        candidate.weight_total += weight;
+        candidate.provenance.push(atom);
+      }
+    }
+  }
+
+  let mut recommendations: Vec<(Decimal, Recommendation)> = vec![];
+  for (target_hash, candidate) in candidates {
+    if candidate.weight_total <= Decimal::ZERO {
+      continue;
+    }
+    let score = candidate.weighted_sum / candidate.weight_total;
+    if let Some(normalized_score) = trust_atom::normalize_value(Some(score.to_string()))? {
+      recommendations.push((
+        score,
+        Recommendation {
+          target_hash,
+          score: normalized_score,
+          provenance: candidate.provenance,
+        },
+      ));
+    }
+  }
+  recommendations.sort_by(|(a, _), (b, _)| b.cmp(a));
+  recommendations.truncate(input.limit);
+
+  Ok(
+    recommendations
+      .into_iter()
+      .map(|(_, recommendation)| recommendation)
+      .collect(),
+  )
+}
+
+/// Agents to draw recommendations from, with the weight of their ratings
+fn recommenders(
+  me: &AnyLinkableHash,
+  my_atoms: &[TrustAtom],
+  via: RecommendVia,
+) -> ExternResult<HashMap<AnyLinkableHash, Decimal>> {
+  let mut recommenders: HashMap<AnyLinkableHash, Decimal> = HashMap::new();
+
+  if matches!(via, RecommendVia::TrustedAgents | RecommendVia::Both) {
+    recommenders.extend(trusted_raters(my_atoms, me));
+  }
+
+  if matches!(via, RecommendVia::SimilarAgents | RecommendVia::Both) {
+    let similar = similar_agents(&SimilarAgentsInput {
+      metric: SimilarityMetric::Pearson,
+      limit: SIMILAR_AGENTS_LIMIT,
+      radius: 1,
+      min_shared_ratings: Some(2),
+    })?;
+    for agent_similarity in similar {
+      let weight = agent_similarity
+        .similarity
+        .and_then(Decimal::from_f64)
+        .filter(|weight| *weight > Decimal::ZERO);
+      if let Some(weight) = weight {
+        let entry = recommenders.entry(agent_similarity.agent).or_default();
+        *entry = (*entry).max(weight);
+      }
+    }
+  }
+
+  Ok(recommenders)
+}
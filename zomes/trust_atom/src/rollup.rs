@@ -0,0 +1,572 @@
+#![allow(clippy::module_name_repetitions)]
+
+use hdk::prelude::*;
+use rust_decimal::prelude::*;
+use std::collections::HashMap;
+use trust_atom_integrity::entries::{
+  CachedRollup, EntryTypes, PrivateCachedRollup, UnitEntryTypes,
+};
+use trust_atom_integrity::headers::build_forward_header;
+use trust_atom_integrity::LinkTypes;
+use trust_atom_types::{
+  CacheRollupInput, CachedRollupOutput, GetCachedRollupInput, RaterWeighting, Rollup, RollupInput,
+  RollupScore, SummarizeTargetInput, TargetSummary, TrustAtom,
+};
+
+use crate::properties::normalize_content;
+use crate::trust_atom;
+
+/// Weight of a rater with no incoming trust, under `RaterWeighting::IncomingTrust`
+const UNTRUSTED_RATER_WEIGHT: Decimal = Decimal::from_parts(1, 0, 0, false, 2); // 0.01
+
+#[derive(Debug, Clone, Default)]
+struct Tally {
+  weighted_sum: Decimal,
+  weight_total: Decimal,
+  raters: usize,
+}
+
+/// Rater weights as seen from the calling agent's TrustGraph
+pub(crate) struct RaterWeights {
+  weighting: RaterWeighting,
+  incoming_trust: HashMap<AnyLinkableHash, Decimal>,
+}
+
+impl RaterWeights {
+  pub(crate) fn for_caller(weighting: Option<RaterWeighting>) -> ExternResult<Self> {
+    let weighting = weighting.unwrap_or(RaterWeighting::Equal);
+    let incoming_trust = match weighting {
+      RaterWeighting::Equal => HashMap::new(),
+      RaterWeighting::IncomingTrust | RaterWeighting::RequirePath => {
+        incoming_trust(&seed_or_me(None)?)?
+      }
+    };
+    Ok(Self {
+      weighting,
+      incoming_trust,
+    })
+  }
+
+  /// `None` means the rater is dropped
+  pub(crate) fn weight(&self, rater: &AnyLinkableHash) -> Option<Decimal> {
+    match self.weighting {
+      RaterWeighting::Equal => Some(Decimal::ONE),
+      RaterWeighting::IncomingTrust => Some(
+        self
+          .incoming_trust
+          .get(rater)
+          .copied()
+          .unwrap_or(UNTRUSTED_RATER_WEIGHT),
+      ),
+      RaterWeighting::RequirePath => self.incoming_trust.get(rater).copied(),
+    }
+  }
+}
+
+/// Trust the caller's graph places in each agent reachable within 2 hops:
+/// the caller's own value for direct hops, and the product of values along the path for
+/// second hops (strongest path wins).  The caller trusts themself fully.
+fn incoming_trust(caller: &AnyLinkableHash) -> ExternResult<HashMap<AnyLinkableHash, Decimal>> {
+  let mut trust: HashMap<AnyLinkableHash, Decimal> = HashMap::new();
+  trust.insert(caller.clone(), Decimal::ONE);
+
+  let first_hop = trust_atom::query(
+    LinkTypes::TrustAtom,
+    Some(caller.clone()),
+    None,
+    None,
+    None,
+    None,
+  )?;
+  let direct = trusted_raters(&first_hop, caller);
+  for (agent, weight) in &direct {
+    raise_to(&mut trust, agent, *weight);
+  }
+
+  for (agent, weight) in direct {
+    let second_hop = trust_atom::query(
+      LinkTypes::TrustAtom,
+      Some(agent.clone()),
+      None,
+      None,
+      None,
+      None,
+    )?;
+    for (next_agent, next_weight) in trusted_raters(&second_hop, &agent) {
+      raise_to(&mut trust, &next_agent, weight * next_weight);
+    }
+  }
+
+  Ok(trust)
+}
+
+fn raise_to(
+  trust: &mut HashMap<AnyLinkableHash, Decimal>,
+  agent: &AnyLinkableHash,
+  weight: Decimal,
+) {
+  let entry = trust.entry(agent.clone()).or_default();
+  *entry = (*entry).max(weight);
+}
+
+/// Weighted mean of all trust atoms on `target`, each rater weighted per `weighting` and by their
+/// confidence
+pub fn summarize_target(input: &SummarizeTargetInput) -> ExternResult<TargetSummary> {
+  let rater_weights = RaterWeights::for_caller(input.weighting)?;
+  let atoms = trust_atom::query(
+    LinkTypes::TrustAtom,
+    None,
+    Some(input.target.clone()),
+    input.content_full.clone(),
+    input.content_starts_with.clone(),
+    None,
+  )?;
+
+  let mut tally = Tally::default();
+  let mut ratings_dropped = 0;
+  for atom in &atoms {
+    match (parse_value(atom), rater_weights.weight(&atom.source_hash)) {
+      (Some(value), Some(weight)) => {
+        let weight = weight * confidence_weight(atom);
+        tally.weighted_sum += value * weight;
+        tally.weight_total += weight;
+        tally.raters += 1;
+      }
+      _ => ratings_dropped += 1,
+    }
+  }
+
+  let value = if tally.weight_total > Decimal::ZERO {
+    trust_atom::normalize_value(Some((tally.weighted_sum / tally.weight_total).to_string()))?
+  } else {
+    None
+  };
+
+  Ok(TargetSummary {
+    target_hash: input.target.clone(),
+    value,
+    ratings_counted: tally.raters,
+    ratings_dropped,
+  })
+}
+
+/// Rolls up a TrustGraph 2 levels deep, starting from `seed`:
+/// - the seed's own matching trust atoms count with weight 1
+/// - matching trust atoms created by agents the seed trusts (value > 0, any content)
+///   count with the seed's value for that agent as weight
+///
+/// Each rater's weight is further scaled per `input.weighting`, as seen from the caller, and each
+/// trust atom's by its confidence.
+pub fn rollup(input: &RollupInput) -> ExternResult<Rollup> {
+  // Compare the seed's atoms against content as `create` stores it
+  let input = &RollupInput {
+    content_full: match &input.content_full {
+      Some(content_full) => Some(normalize_content(content_full)?),
+      None => None,
+    },
+    content_starts_with: match &input.content_starts_with {
+      Some(content_starts_with) => Some(normalize_content(content_starts_with)?),
+      None => None,
+    },
+    ..input.clone()
+  };
+  let rater_weights = RaterWeights::for_caller(input.weighting)?;
+  let seed = seed_or_me(input.seed.clone())?;
+  let first_hop = trust_atom::query(
+    LinkTypes::TrustAtom,
+    Some(seed.clone()),
+    None,
+    None,
+    None,
+    None,
+  )?;
+
+  let mut tallies: HashMap<AnyLinkableHash, Tally> = HashMap::new();
+
+  if let Some(seed_weight) = rater_weights.weight(&seed) {
+    for atom in &first_hop {
+      if content_matches(atom, input) {
+        tally(&mut tallies, atom, seed_weight);
+      }
+    }
+  }
+
+  for (rater, weight) in trusted_raters(&first_hop, &seed) {
+    let weight = match rater_weights.weight(&rater) {
+      Some(rater_weight) => weight * rater_weight,
+      None => continue,
+    };
+    let second_hop = trust_atom::query(
+      LinkTypes::TrustAtom,
+      Some(rater),
+      None,
+      input.content_full.clone(),
+      input.content_starts_with.clone(),
+      None,
+    )?;
+    for atom in &second_hop {
+      tally(&mut tallies, atom, weight);
+    }
+  }
+
+  let mut scores: Vec<(Decimal, RollupScore)> = vec![];
+  for (target_hash, tally) in tallies {
+    if tally.weight_total <= Decimal::ZERO {
+      continue;
+    }
+    let score = tally.weighted_sum / tally.weight_total;
+    if let Some(value) = trust_atom::normalize_value(Some(score.to_string()))? {
+      scores.push((
+        score,
+        RollupScore {
+          target_hash,
+          value,
+          raters: tally.raters,
+        },
+      ));
+    }
+  }
+  scores.sort_by(|(a, _), (b, _)| b.cmp(a));
+
+  Ok(Rollup {
+    seed,
+    scores: scores.into_iter().map(|(_, score)| score).collect(),
+  })
+}
+
+pub fn cache_rollup(input: &CacheRollupInput) -> ExternResult<CachedRollupOutput> {
+  let rollup = rollup(&input.rollup)?;
+
+  let cached = CachedRollup {
+    seed: rollup.seed.clone(),
+    cache_key: input.rollup.cache_key(),
+    first_hop_fingerprint: rollup_fingerprint(&rollup.seed, input.rollup.weighting)?,
+    computed_at: sys_time()?,
+    scores: rollup.scores.clone(),
+  };
+
+  if input.private {
+    create_entry(EntryTypes::PrivateCachedRollup(PrivateCachedRollup(
+      cached.clone(),
+    )))?;
+  } else {
+    create_entry(EntryTypes::CachedRollup(cached.clone()))?;
+  }
+
+  Ok(CachedRollupOutput {
+    rollup: Some(rollup),
+    computed_at: Some(cached.computed_at),
+    stale: false,
+  })
+}
+
+/// Returns the most recent cached rollup for the given seed and parameters,
+/// reporting whether it is stale (ie the seed's first hop, or for weighted rollups the caller's,
+/// changed since it was computed)
+pub fn get_cached_rollup(input: &GetCachedRollupInput) -> ExternResult<CachedRollupOutput> {
+  let seed = seed_or_me(input.rollup.seed.clone())?;
+
+  let cached = find_cached_rollup(&seed, &input.rollup.cache_key())?;
+  let stale = match &cached {
+    Some(cached) => {
+      cached.first_hop_fingerprint != rollup_fingerprint(&seed, input.rollup.weighting)?
+    }
+    None => true,
+  };
+
+  if stale && input.refresh_if_stale {
+    return cache_rollup(&CacheRollupInput {
+      rollup: input.rollup.clone(),
+      private: input.private,
+    });
+  }
+
+  Ok(match cached {
+    Some(cached) => CachedRollupOutput {
+      rollup: Some(Rollup {
+        seed: cached.seed,
+        scores: cached.scores,
+      }),
+      computed_at: Some(cached.computed_at),
+      stale,
+    },
+    None => CachedRollupOutput {
+      rollup: None,
+      computed_at: None,
+      stale,
+    },
+  })
+}
+
+/// `first_hop_fingerprint` of the seed, followed by the caller's when raters are weighted by the
+/// caller's TrustGraph, which the rollup then also depends on
+fn rollup_fingerprint(
+  seed: &AnyLinkableHash,
+  weighting: Option<RaterWeighting>,
+) -> ExternResult<Vec<u8>> {
+  let mut fingerprint = first_hop_fingerprint(seed)?;
+  match weighting.unwrap_or(RaterWeighting::Equal) {
+    RaterWeighting::Equal => {}
+    RaterWeighting::IncomingTrust | RaterWeighting::RequirePath => {
+      fingerprint.extend(first_hop_fingerprint(&seed_or_me(None)?)?);
+    }
+  }
+  Ok(fingerprint)
+}
+
+/// Hash over the create-link hashes of all of the seed's trust atoms;
+/// changes whenever one of them is created or deleted
+fn first_hop_fingerprint(seed: &AnyLinkableHash) -> ExternResult<Vec<u8>> {
+  let links = get_links(
+    seed.clone(),
+    LinkTypes::TrustAtom,
+    Some(LinkTag::new(build_forward_header())),
+  )?;
+
+  let mut link_hashes: Vec<Vec<u8>> = links
+    .into_iter()
+    .map(|link| link.create_link_hash.get_raw_39().to_vec())
+    .collect();
+  link_hashes.sort();
+
+  hdk::hash::hash_blake2b(link_hashes.concat(), 32)
+}
+
+fn find_cached_rollup(
+  seed: &AnyLinkableHash,
+  cache_key: &str,
+) -> ExternResult<Option<CachedRollup>> {
+  let mut candidates: Vec<CachedRollup> = vec![];
+
+  let public_filter = ChainQueryFilter::new()
+    .entry_type(UnitEntryTypes::CachedRollup.try_into()?)
+    .include_entries(true);
+  for record in hdk::prelude::query(public_filter)? {
+    if let Some(cached) = record
+      .entry()
+      .to_app_option::<CachedRollup>()
+      .map_err(|e| wasm_error!(WasmErrorInner::Serialize(e)))?
+    {
+      candidates.push(cached);
+    }
+  }
+
+  let private_filter = ChainQueryFilter::new()
+    .entry_type(UnitEntryTypes::PrivateCachedRollup.try_into()?)
+    .include_entries(true);
+  for record in hdk::prelude::query(private_filter)? {
+    if let Some(PrivateCachedRollup(cached)) = record
+      .entry()
+      .to_app_option::<PrivateCachedRollup>()
+      .map_err(|e| wasm_error!(WasmErrorInner::Serialize(e)))?
+    {
+      candidates.push(cached);
+    }
+  }
+
+  Ok(
+    candidates
+      .into_iter()
+      .filter(|cached| cached.seed == *seed && cached.cache_key == cache_key)
+      .max_by_key(|cached| cached.computed_at),
+  )
+}
+
+pub(crate) fn seed_or_me(seed: Option<AnyLinkableHash>) -> ExternResult<AnyLinkableHash> {
+  match seed {
+    Some(seed) => Ok(seed),
+    None => Ok(AnyLinkableHash::from(agent_info()?.agent_initial_pubkey)),
+  }
+}
+
+/// Targets of the seed's positive trust atoms, with the seed's (strongest) value as weight
+pub(crate) fn trusted_raters(
+  first_hop: &[TrustAtom],
+  seed: &AnyLinkableHash,
+) -> HashMap<AnyLinkableHash, Decimal> {
+  let mut raters: HashMap<AnyLinkableHash, Decimal> = HashMap::new();
+  for atom in first_hop {
+    if atom.target_hash == *seed {
+      continue;
+    }
+    if let Some(weight) = parse_value(atom) {
+      if weight > Decimal::ZERO {
+        raise_to(&mut raters, &atom.target_hash, weight);
+      }
+    }
+  }
+  raters
+}
+
+fn tally(tallies: &mut HashMap<AnyLinkableHash, Tally>, atom: &TrustAtom, weight: Decimal) {
+  if let Some(value) = parse_value(atom) {
+    let weight = weight * confidence_weight(atom);
+    let tally = tallies.entry(atom.target_hash.clone()).or_default();
+    tally.weighted_sum += value * weight;
+    tally.weight_total += weight;
+    tally.raters += 1;
+  }
+}
+
+pub(crate) fn parse_value(atom: &TrustAtom) -> Option<Decimal> {
+  atom
+    .value
+    .as_ref()
+    .and_then(|value| Decimal::from_str(value).ok())
+}
+
+/// Factor to weight a trust atom's value by: its confidence, or 1 if it has none
+pub(crate) fn confidence_weight(atom: &TrustAtom) -> Decimal {
+  atom
+    .confidence
+    .as_ref()
+    .and_then(|confidence| Decimal::from_str(confidence).ok())
+    .map_or(Decimal::ONE, |confidence| {
+      confidence.clamp(Decimal::ZERO, Decimal::ONE)
+    })
+}
+
+fn content_matches(atom: &TrustAtom, input: &RollupInput) -> bool {
+  let content = atom.content.clone().unwrap_or_default();
+  match (&input.content_full, &input.content_starts_with) {
+    (Some(content_full), _) => content == *content_full,
+    (None, Some(content_starts_with)) => content.starts_with(content_starts_with.as_str()),
+    (None, None) => true,
+  }
+}
+
+#[cfg(test)]
+#[allow(clippy::unwrap_used)]
+#[allow(non_snake_case)]
+mod tests {
+
+  use super::*; // allows testing of private functions
+
+  fn fake_hash(byte: u8) -> AnyLinkableHash {
+    AnyLinkableHash::from(EntryHash::from_raw_36(vec![byte; 36]))
+  }
+
+  fn atom(source: u8, target: u8, content: &str, value: &str) -> TrustAtom {
+    TrustAtom {
+      source_hash: fake_hash(source),
+      target_hash: fake_hash(target),
+      content: Some(content.to_string()),
+      display_content: None,
+      confidence: None,
+      value: Some(value.to_string()),
+      extra: None,
+    }
+  }
+
+  #[test]
+  fn test_content_matches() {
+    let input = RollupInput {
+      seed: None,
+      content_full: None,
+      content_starts_with: Some("sushi".to_string()),
+      weighting: None,
+    };
+    assert!(content_matches(&atom(1, 2, "sushi joint", ".8"), &input));
+    assert!(!content_matches(&atom(1, 2, "sush", ".8"), &input));
+
+    let input = RollupInput {
+      seed: None,
+      content_full: Some("sushi".to_string()),
+      content_starts_with: None,
+      weighting: None,
+    };
+    assert!(content_matches(&atom(1, 2, "sushi", ".8"), &input));
+    assert!(!content_matches(&atom(1, 2, "sushi joint", ".8"), &input));
+  }
+
+  #[test]
+  fn test_trusted_raters__positive_values_only() {
+    let first_hop = [
+      atom(1, 2, "cooking", ".500000000"),
+      atom(1, 2, "sushi", ".900000000"),
+      atom(1, 3, "spam", "-.999999999"),
+      atom(1, 1, "me", ".999999999"),
+    ];
+    let raters = trusted_raters(&first_hop, &fake_hash(1));
+
+    assert_eq!(raters.len(), 1);
+    assert_eq!(
+      raters.get(&fake_hash(2)).unwrap().to_string(),
+      "0.900000000"
+    );
+  }
+
+  #[test]
+  fn test_rater_weights() {
+    let incoming_trust = HashMap::from([
+      (fake_hash(1), Decimal::ONE),
+      (fake_hash(2), Decimal::from_str("0.5").unwrap()),
+    ]);
+    let weights = |weighting| RaterWeights {
+      weighting,
+      incoming_trust: incoming_trust.clone(),
+    };
+
+    let equal = weights(RaterWeighting::Equal);
+    assert_eq!(equal.weight(&fake_hash(2)), Some(Decimal::ONE));
+    assert_eq!(equal.weight(&fake_hash(3)), Some(Decimal::ONE));
+
+    let incoming = weights(RaterWeighting::IncomingTrust);
+    assert_eq!(incoming.weight(&fake_hash(2)).unwrap().to_string(), "0.5");
+    assert_eq!(incoming.weight(&fake_hash(3)).unwrap().to_string(), "0.01");
+
+    let require_path = weights(RaterWeighting::RequirePath);
+    assert_eq!(require_path.weight(&fake_hash(1)), Some(Decimal::ONE));
+    assert_eq!(require_path.weight(&fake_hash(3)), None);
+  }
+
+  #[test]
+  fn test_confidence_weight() {
+    let with_confidence = |confidence: Option<&str>| TrustAtom {
+      confidence: confidence.map(ToString::to_string),
+      ..atom(1, 2, "sushi", ".8")
+    };
+    assert_eq!(confidence_weight(&with_confidence(None)), Decimal::ONE);
+    assert_eq!(
+      confidence_weight(&with_confidence(Some(".250000000"))).to_string(),
+      "0.250000000"
+    );
+    assert_eq!(
+      confidence_weight(&with_confidence(Some("0"))),
+      Decimal::ZERO
+    );
+    // other agents' tags are not validated
+    assert_eq!(
+      confidence_weight(&with_confidence(Some("-.5"))),
+      Decimal::ZERO
+    );
+    assert_eq!(
+      confidence_weight(&with_confidence(Some("sure"))),
+      Decimal::ONE
+    );
+  }
+
+  #[test]
+  fn test_tally__weighted_by_confidence() {
+    let unsure = TrustAtom {
+      confidence: Some(".250000000".to_string()),
+      ..atom(1, 3, "sushi", "-.800000000")
+    };
+    let mut tallies = HashMap::new();
+    tally(
+      &mut tallies,
+      &atom(2, 3, "sushi", ".800000000"),
+      Decimal::ONE,
+    );
+    tally(&mut tallies, &unsure, Decimal::ONE);
+
+    let tally = tallies.get(&fake_hash(3)).unwrap();
+    assert_eq!(tally.raters, 2);
+    assert_eq!(
+      (tally.weighted_sum / tally.weight_total)
+        .round_dp(9)
+        .to_string(),
+      "0.480000000"
+    );
+  }
+}
@@ -0,0 +1,224 @@
+#![allow(clippy::module_name_repetitions)]
+
+use hdk::prelude::*;
+use rust_decimal::prelude::*;
+use std::collections::{HashMap, HashSet};
+use trust_atom_integrity::LinkTypes;
+use trust_atom_types::{
+  AgentSimilarity, AgentSimilarityInput, SimilarAgentsInput, SimilarityMetric,
+};
+
+use crate::rollup::{parse_value, seed_or_me};
+use crate::trust_atom;
+
+const MAX_CRAWL_RADIUS: u8 = 2;
+
+/// An agent's ratings, keyed by (target, content); repeated ratings are averaged
+type RatingVector = HashMap<(AnyLinkableHash, String), f64>;
+
+pub fn agent_similarity(input: &AgentSimilarityInput) -> ExternResult<AgentSimilarity> {
+  let compared_to = seed_or_me(input.compared_to.clone())?;
+  let ratings = rating_vector(&compared_to)?;
+
+  similarity_to(&ratings, &input.agent, input.metric)
+}
+
+/// Top `limit` agents whose ratings are most similar to mine, among agents found within
+/// `radius` hops of me
+pub fn similar_agents(input: &SimilarAgentsInput) -> ExternResult<Vec<AgentSimilarity>> {
+  if input.radius == 0 || input.radius > MAX_CRAWL_RADIUS {
+    return Err(wasm_error!(
+      "`radius` must be in the range 1..{}, but got: `{}`",
+      MAX_CRAWL_RADIUS,
+      input.radius
+    ));
+  }
+  let min_shared_ratings = input.min_shared_ratings.unwrap_or(1);
+
+  let me = seed_or_me(None)?;
+  let my_ratings = rating_vector(&me)?;
+
+  let mut similar: Vec<AgentSimilarity> = vec![];
+  for candidate in co_raters(&me, input.radius)? {
+    let candidate_similarity = similarity_to(&my_ratings, &candidate, input.metric)?;
+    if candidate_similarity.similarity.is_some()
+      && candidate_similarity.shared_ratings >= min_shared_ratings
+    {
+      similar.push(candidate_similarity);
+    }
+  }
+
+  similar.sort_by(|a, b| {
+    b.similarity
+      .partial_cmp(&a.similarity)
+      .unwrap_or(std::cmp::Ordering::Equal)
+  });
+  similar.truncate(input.limit);
+
+  Ok(similar)
+}
+
+fn similarity_to(
+  ratings: &RatingVector,
+  agent: &AnyLinkableHash,
+  metric: SimilarityMetric,
+) -> ExternResult<AgentSimilarity> {
+  let agent_ratings = rating_vector(agent)?;
+  let (xs, ys) = shared_ratings(ratings, &agent_ratings);
+
+  let similarity = match metric {
+    SimilarityMetric::Cosine => cosine_similarity(&xs, &ys),
+    SimilarityMetric::Pearson => pearson_correlation(&xs, &ys),
+  };
+
+  Ok(AgentSimilarity {
+    agent: agent.clone(),
+    similarity,
+    shared_ratings: xs.len(),
+  })
+}
+
+pub(crate) fn rating_vector(agent: &AnyLinkableHash) -> ExternResult<RatingVector> {
+  let atoms = trust_atom::query(
+    LinkTypes::TrustAtom,
+    Some(agent.clone()),
+    None,
+    None,
+    None,
+    None,
+  )?;
+
+  let mut sums: HashMap<(AnyLinkableHash, String), (f64, f64)> = HashMap::new();
+  for atom in &atoms {
+    if let Some(value) = parse_value(atom).and_then(|value| value.to_f64()) {
+      let key = (
+        atom.target_hash.clone(),
+        atom.content.clone().unwrap_or_default(),
+      );
+      let (sum, count) = sums.entry(key).or_default();
+      *sum += value;
+      *count += 1.0;
+    }
+  }
+
+  Ok(
+    sums
+      .into_iter()
+      .map(|(key, (sum, count))| (key, sum / count))
+      .collect(),
+  )
+}
+
+/// Agents (other than `me`) who rated something that an agent within the previous hop rated
+fn co_raters(me: &AnyLinkableHash, radius: u8) -> ExternResult<HashSet<AnyLinkableHash>> {
+  let mut seen: HashSet<AnyLinkableHash> = HashSet::from([me.clone()]);
+  let mut frontier: Vec<AnyLinkableHash> = vec![me.clone()];
+
+  for _ in 0..radius {
+    let mut next_frontier: Vec<AnyLinkableHash> = vec![];
+    let mut targets: HashSet<AnyLinkableHash> = HashSet::new();
+    for agent in &frontier {
+      for atom in trust_atom::query(
+        LinkTypes::TrustAtom,
+        Some(agent.clone()),
+        None,
+        None,
+        None,
+        None,
+      )? {
+        targets.insert(atom.target_hash);
+      }
+    }
+    for target in targets {
+      for atom in trust_atom::query(LinkTypes::TrustAtom, None, Some(target), None, None, None)? {
+        if seen.insert(atom.source_hash.clone()) {
+          next_frontier.push(atom.source_hash);
+        }
+      }
+    }
+    frontier = next_frontier;
+  }
+
+  seen.remove(me);
+  Ok(seen)
+}
+
+fn shared_ratings(a: &RatingVector, b: &RatingVector) -> (Vec<f64>, Vec<f64>) {
+  a.iter()
+    .filter_map(|(key, x)| b.get(key).map(|y| (*x, *y)))
+    .unzip()
+}
+
+fn cosine_similarity(xs: &[f64], ys: &[f64]) -> Option<f64> {
+  let dot: f64 = xs.iter().zip(ys).map(|(x, y)| x * y).sum();
+  let norm_x = xs.iter().map(|x| x * x).sum::<f64>().sqrt();
+  let norm_y = ys.iter().map(|y| y * y).sum::<f64>().sqrt();
+
+  if xs.is_empty() || norm_x == 0.0 || norm_y == 0.0 {
+    None
+  } else {
+    Some(dot / (norm_x * norm_y))
+  }
+}
+
+#[allow(clippy::cast_precision_loss)]
+fn pearson_correlation(xs: &[f64], ys: &[f64]) -> Option<f64> {
+  if xs.len() < 2 {
+    return None;
+  }
+  let n = xs.len() as f64;
+  let mean_x = xs.iter().sum::<f64>() / n;
+  let mean_y = ys.iter().sum::<f64>() / n;
+
+  let xs_centered: Vec<f64> = xs.iter().map(|x| x - mean_x).collect();
+  let ys_centered: Vec<f64> = ys.iter().map(|y| y - mean_y).collect();
+
+  cosine_similarity(&xs_centered, &ys_centered)
+}
+
+#[cfg(test)]
+#[allow(clippy::unwrap_used)]
+#[allow(clippy::float_cmp)]
+#[allow(non_snake_case)]
+mod tests {
+
+  use super::*; // allows testing of private functions
+
+  fn assert_close(actual: f64, expected: f64) {
+    assert!(
+      (actual - expected).abs() < 1e-9,
+      "Expected: `{expected}`, but got: `{actual}`"
+    );
+  }
+
+  #[test]
+  fn test_cosine_similarity() {
+    assert_close(cosine_similarity(&[0.5, 0.5], &[0.9, 0.9]).unwrap(), 1.0);
+    assert_close(cosine_similarity(&[0.5, -0.5], &[-0.5, 0.5]).unwrap(), -1.0);
+    assert_close(cosine_similarity(&[1.0, 0.0], &[0.0, 1.0]).unwrap(), 0.0);
+  }
+
+  #[test]
+  fn test_cosine_similarity__undefined() {
+    assert_eq!(cosine_similarity(&[], &[]), None);
+    assert_eq!(cosine_similarity(&[0.0, 0.0], &[0.5, 0.5]), None);
+  }
+
+  #[test]
+  fn test_pearson_correlation() {
+    assert_close(
+      pearson_correlation(&[0.1, 0.2, 0.3], &[0.5, 0.6, 0.7]).unwrap(),
+      1.0,
+    );
+    assert_close(
+      pearson_correlation(&[0.1, 0.2, 0.3], &[0.7, 0.6, 0.5]).unwrap(),
+      -1.0,
+    );
+  }
+
+  #[test]
+  fn test_pearson_correlation__undefined() {
+    assert_eq!(pearson_correlation(&[0.5], &[0.5]), None);
+    assert_eq!(pearson_correlation(&[0.5, 0.5], &[0.1, 0.9]), None);
+  }
+}
@@ -0,0 +1,41 @@
+#![allow(clippy::module_name_repetitions)]
+
+use hdk::prelude::*;
+use trust_atom_integrity::entries::{EntryTypes, NamespacedStringTarget, StringTarget};
+use trust_atom_types::string_target::normalize;
+use trust_atom_types::StringTargetInput;
+
+/// Entry hash of the normalized string target, to use as a trust atom target.
+/// Idempotent: the entry is only created if nobody has created it yet.
+pub fn create_string_target(input: &StringTargetInput) -> ExternResult<EntryHash> {
+  let string_target = string_target(input)?;
+  let target_hash = hash_entry(string_target.clone())?;
+
+  if get(target_hash.clone(), GetOptions::default())?.is_none() {
+    create_entry(string_target)?;
+  }
+
+  Ok(target_hash)
+}
+
+/// Entry hash of the string target with this text (after normalization), if it has been created
+pub fn lookup_string_target(input: &StringTargetInput) -> ExternResult<Option<EntryHash>> {
+  let target_hash = hash_entry(string_target(input)?)?;
+
+  match get(target_hash.clone(), GetOptions::default())? {
+    Some(_) => Ok(Some(target_hash)),
+    None => Ok(None),
+  }
+}
+
+/// `StringTarget` without a namespace, so that targets from before namespaces are found
+fn string_target(input: &StringTargetInput) -> ExternResult<EntryTypes> {
+  let normalized = normalize(input).map_err(|error| wasm_error!("{}", error))?;
+  Ok(match normalized.namespace {
+    Some(namespace) => EntryTypes::NamespacedStringTarget(NamespacedStringTarget {
+      namespace,
+      text: normalized.text,
+    }),
+    None => EntryTypes::StringTarget(StringTarget(normalized.text)),
+  })
+}
@@ -1,8 +1,12 @@
 #![allow(clippy::module_name_repetitions)]
 
 use hdk::prelude::*;
-use trust_atom_integrity::entries::{EntryTypes, Example, StringTarget};
+use trust_atom_codec::{Direction, TagVersion, TrustAtomTag};
+use trust_atom_integrity::entries::{EntryTypes, Example, ExternalTarget, Extra};
 use trust_atom_integrity::LinkTypes;
+use trust_atom_types::TrustAtomInput;
+
+use crate::trust_atom::{calc_extra_hash, create_extra, create_link_pair, resolve_value};
 
 #[derive(Serialize, Deserialize, Debug)]
 pub struct StringLinkTag(pub String);
@@ -36,11 +40,47 @@ fn link_tag(tag: String) -> ExternResult<LinkTag> {
   }
 }
 
-pub fn create_string_target(input: String) -> ExternResult<EntryHash> {
-  create_entry(EntryTypes::StringTarget(StringTarget(input.clone())))?;
+/// Trust atom links in the version 1 format, as written before tags had a version byte
+#[allow(clippy::needless_pass_by_value)]
+pub fn create_v1_trust_atom(input: TrustAtomInput) -> ExternResult<()> {
+  let agent_address = AnyLinkableHash::from(agent_info()?.agent_initial_pubkey);
+  let (value, extra) = resolve_value(input.value, input.extra)?;
+  let extra_hash = match extra {
+    Some(fields) => {
+      create_extra(fields.clone())?;
+      Some(calc_extra_hash(Extra { fields })?.to_string())
+    }
+    None => None,
+  };
+  let forward_tag = TrustAtomTag {
+    direction: Direction::Forward,
+    version: TagVersion::V1,
+    content: input.content,
+    value,
+    bucket: Some("123456789".to_string()),
+    extra_hash,
+    display_content: None,
+    confidence: None,
+  };
+  create_link_pair(
+    LinkTypes::TrustAtom,
+    &agent_address,
+    &input.target,
+    &forward_tag,
+  )
+}
 
-  let target_hash = hash_entry(EntryTypes::StringTarget(StringTarget(input)))?;
-  Ok(target_hash)
+/// External target link from any hash, eg one which isn't the hash of `uri`
+pub fn create_external_target_link(external_hash: ExternalHash, uri: String) -> ExternResult<()> {
+  let external_target = ExternalTarget { uri };
+  create_entry(EntryTypes::ExternalTarget(external_target.clone()))?;
+  create_link(
+    external_hash,
+    hash_entry(external_target)?,
+    LinkTypes::ExternalTarget,
+    (),
+  )?;
+  Ok(())
 }
 
 pub fn create_test_entry(input: Example) -> ExternResult<ActionHash> {
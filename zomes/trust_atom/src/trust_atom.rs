@@ -2,68 +2,268 @@
 
 use hdk::prelude::*;
 use rust_decimal::prelude::*;
-use std::collections::BTreeMap;
+use std::collections::{BTreeMap, HashSet};
+use trust_atom_codec::{
+  check_no_nul, decode, encode, encode_prefix, Direction, TagField, TagVersion, TrustAtomTag,
+};
 use trust_atom_integrity::entries::{EntryTypes, Extra};
-use trust_atom_integrity::headers::{
-  LINK_TAG_ARROW_FORWARD, LINK_TAG_ARROW_REVERSE, LINK_TAG_HEADER, UNICODE_NUL_STR,
+use trust_atom_integrity::headers::{build_forward_header, build_reverse_header};
+use trust_atom_types::value::{TrustAtomValue, ORIGINAL_VALUE_EXTRA_KEY};
+use trust_atom_types::{
+  CreateTrustAtomResult, DeleteReport, LinkDecodeError, QueryOutput, TrustAtom, TrustAtomInput,
 };
-use trust_atom_integrity::LinkTypes;
-use trust_atom_types::TrustAtom;
 
-#[derive(Debug, Clone)]
-enum LinkDirection {
-  Forward,
-  Reverse,
-}
+use crate::properties::{normalize_content, tag_version};
 
-pub fn create(
+/// `link_type` is the link type to store trust atoms under: `trust_atom_integrity`'s
+/// `LinkTypes::TrustAtom`, or a host zome's own, eg its `LinkTypes::Rating`
+pub fn create<L>(
+  link_type: L,
   target: AnyLinkableHash,
   content: Option<String>,
-  value: Option<String>,
+  value: Option<TrustAtomValue>,
+  confidence: Option<String>,
   extra: Option<BTreeMap<String, String>>,
-) -> ExternResult<TrustAtom> {
+) -> ExternResult<TrustAtom>
+where
+  L: Clone,
+  ScopedLinkType: TryFrom<L, Error = WasmError>,
+{
   let agent_address = AnyLinkableHash::from(agent_info()?.agent_initial_pubkey);
+  let new_trust_atom = prepare(content, value, confidence, extra)?;
+  write_trust_atom(link_type, agent_address, target, new_trust_atom)
+}
 
-  let bucket = create_bucket()?;
+/// A trust atom which has been checked, and is ready to write
+struct NewTrustAtom {
+  forward_tag: TrustAtomTag,
+  /// As given, or converted from a typed value
+  value: Option<String>,
+  extra: Option<BTreeMap<String, String>>,
+}
 
-  let extra_entry_hash_string = match extra.clone() {
-    Some(x) => Some(create_extra(x)?),
+/// Resolves the value and builds the forward link tag, without writing anything
+fn prepare(
+  content: Option<String>,
+  value: Option<TrustAtomValue>,
+  confidence: Option<String>,
+  extra: Option<BTreeMap<String, String>>,
+) -> ExternResult<NewTrustAtom> {
+  let (value, extra) = resolve_value(value, extra)?;
+  let forward_tag = new_forward_tag(content, value.clone(), confidence, extra.as_ref())?;
+  Ok(NewTrustAtom {
+    forward_tag,
+    value,
+    extra,
+  })
+}
+
+/// Writes the `Extra` entry, if any, and the link pair
+fn write_trust_atom<L>(
+  link_type: L,
+  agent_address: AnyLinkableHash,
+  target: AnyLinkableHash,
+  new_trust_atom: NewTrustAtom,
+) -> ExternResult<TrustAtom>
+where
+  L: Clone,
+  ScopedLinkType: TryFrom<L, Error = WasmError>,
+{
+  let NewTrustAtom {
+    forward_tag,
+    value,
+    extra,
+  } = new_trust_atom;
+
+  if let Some(extra) = extra.clone() {
+    create_extra(extra)?;
+  }
+  create_link_pair(link_type, &agent_address, &target, &forward_tag)?;
+
+  let trust_atom = TrustAtom {
+    source_hash: agent_address,
+    target_hash: target,
+    content: forward_tag.content,
+    display_content: forward_tag.display_content,
+    value,
+    confidence: forward_tag.confidence,
+    extra,
+  };
+  Ok(trust_atom)
+}
+
+/// Tag for a new trust atom's forward link, checked against the tag size limits before anything
+/// is written
+fn new_forward_tag(
+  content: Option<String>,
+  value: Option<String>,
+  confidence: Option<String>,
+  extra: Option<&BTreeMap<String, String>>,
+) -> ExternResult<TrustAtomTag> {
+  let extra_hash = match extra {
+    Some(fields) => Some(
+      calc_extra_hash(Extra {
+        fields: fields.clone(),
+      })?
+      .to_string(),
+    ),
     None => None,
   };
+  let normalized_content = match &content {
+    Some(content) => Some(normalize_content(content)?),
+    None => None,
+  };
+  let display_content = content.filter(|content| Some(content) != normalized_content.as_ref());
+
+  let forward_tag = TrustAtomTag {
+    direction: Direction::Forward,
+    version: tag_version()?,
+    content: normalized_content,
+    value: normalize_value(value)?,
+    bucket: Some(create_bucket()?),
+    extra_hash,
+    display_content,
+    confidence: normalize_confidence(confidence)?,
+  };
+  encode(&forward_tag).map_err(|error| wasm_error!("{}", error))?;
+  Ok(forward_tag)
+}
 
-  let chunks = [
-    content.clone(),
-    normalize_value(value.clone())?,
-    Some(bucket),
-    extra_entry_hash_string,
-  ];
-  let forward_link_tag = create_link_tag(&LinkDirection::Forward, &chunks);
-  let reverse_link_tag = create_link_tag(&LinkDirection::Reverse, &chunks);
+/// Forward link from `agent_address` to `target` with `forward_tag`, and the matching reverse link
+pub(crate) fn create_link_pair<L>(
+  link_type: L,
+  agent_address: &AnyLinkableHash,
+  target: &AnyLinkableHash,
+  forward_tag: &TrustAtomTag,
+) -> ExternResult<()>
+where
+  L: Clone,
+  ScopedLinkType: TryFrom<L, Error = WasmError>,
+{
+  let reverse_tag = TrustAtomTag {
+    direction: Direction::Reverse,
+    ..forward_tag.clone()
+  };
 
   create_link(
     agent_address.clone(),
     target.clone(),
-    LinkTypes::TrustAtom,
-    forward_link_tag,
+    link_type.clone(),
+    LinkTag::new(encode(forward_tag).map_err(|error| wasm_error!("{}", error))?),
   )?;
   create_link(
     target.clone(),
     agent_address.clone(),
-    LinkTypes::TrustAtom,
-    reverse_link_tag,
+    link_type,
+    LinkTag::new(encode(&reverse_tag).map_err(|error| wasm_error!("{}", error))?),
   )?;
+  Ok(())
+}
 
-  let trust_atom = TrustAtom {
-    source_hash: agent_address,
-    target_hash: target,
-    content,
-    value,
-    extra,
-  };
-  Ok(trust_atom)
+/// Validates every input before creating any trust atom, so that invalid items are reported
+/// and skipped while the valid ones are all committed together at the end of the zome call.  An
+/// item which fails to be written is reported the same way.
+pub fn create_many<L>(
+  link_type: L,
+  inputs: &[TrustAtomInput],
+) -> ExternResult<Vec<CreateTrustAtomResult>>
+where
+  L: Clone,
+  ScopedLinkType: TryFrom<L, Error = WasmError>,
+{
+  let agent_address = AnyLinkableHash::from(agent_info()?.agent_initial_pubkey);
+  let prepared: Vec<Result<NewTrustAtom, String>> = inputs
+    .iter()
+    .map(|input| {
+      prepare(
+        input.content.clone(),
+        input.value.clone(),
+        input.confidence.clone(),
+        input.extra.clone(),
+      )
+      .map_err(error_message)
+    })
+    .collect();
+
+  Ok(
+    inputs
+      .iter()
+      .zip(prepared)
+      .map(|(input, prepared)| {
+        let created = prepared.and_then(|new_trust_atom| {
+          write_trust_atom(
+            link_type.clone(),
+            agent_address.clone(),
+            input.target.clone(),
+            new_trust_atom,
+          )
+          .map_err(error_message)
+        });
+        match created {
+          Ok(trust_atom) => CreateTrustAtomResult {
+            trust_atom: Some(trust_atom),
+            error: None,
+          },
+          Err(error) => CreateTrustAtomResult {
+            trust_atom: None,
+            error: Some(error),
+          },
+        }
+      })
+      .collect(),
+  )
+}
+
+/// Message of a guest error, as returned to clients; other errors in full
+fn error_message(error: WasmError) -> String {
+  match error.error {
+    WasmErrorInner::Guest(message) => message,
+    error => format!("{error:?}"),
+  }
+}
+
+/// Deletes all of my trust atoms about `target`
+pub fn delete<L>(link_type: L, target: AnyLinkableHash) -> ExternResult<DeleteReport>
+where
+  L: LinkTypeFilterExt + Clone,
+{
+  let agent_pubkey = agent_info()?.agent_initial_pubkey;
+
+  // Forward Links
+  let forward_links = get_links(agent_pubkey.clone(), link_type.clone(), None)?;
+  for link in forward_links.clone() {
+    if link.target == target && link.tag.0.starts_with(&build_forward_header()) {
+      delete_link(link.create_link_hash)?;
+    }
+  }
+
+  // Reverse Links
+  let reverse_links = get_links(target, link_type, None)?;
+  for link in reverse_links.clone() {
+    if link.target == AnyLinkableHash::from(agent_pubkey.clone())
+      && link.tag.0.starts_with(&build_reverse_header())
+    {
+      delete_link(link.create_link_hash)?;
+    }
+  }
+
+  if forward_links.len() == reverse_links.len() {
+    let trust_atoms_deleted = forward_links.len();
+    Ok(DeleteReport {
+      forward_links_deleted: forward_links.len(),
+      backward_links_deleted: reverse_links.len(),
+      trust_atoms_deleted,
+    })
+  } else {
+    Err(wasm_error!(
+      "Number of deleted forward links ({}) does not match number of deleted reverse links ({})",
+      forward_links.len(),
+      reverse_links.len()
+    ))
+  }
 }
 
-fn create_bucket() -> ExternResult<String> {
+pub(crate) fn create_bucket() -> ExternResult<String> {
   let bucket_bytes = random_bytes(9)?.into_vec();
   Ok(create_bucket_string(&bucket_bytes))
 }
@@ -77,13 +277,10 @@ fn create_bucket_string(bucket_bytes: &[u8]) -> String {
   bucket
 }
 
-fn create_extra(input: BTreeMap<String, String>) -> ExternResult<String> {
+pub(crate) fn create_extra(input: BTreeMap<String, String>) -> ExternResult<ActionHash> {
   let entry = Extra { fields: input };
 
-  create_entry(EntryTypes::Extra(entry.clone()))?;
-
-  let entry_hash_string = calc_extra_hash(entry)?.to_string();
-  Ok(entry_hash_string)
+  create_entry(EntryTypes::Extra(entry))
 }
 
 pub fn calc_extra_hash(input: Extra) -> ExternResult<EntryHash> {
@@ -91,70 +288,84 @@ pub fn calc_extra_hash(input: Extra) -> ExternResult<EntryHash> {
   Ok(hash)
 }
 
-fn normalize_value(value_str: Option<String>) -> ExternResult<Option<String>> {
-  match value_str {
-    Some(value_str) => match Decimal::from_str(value_str.as_str()) {
-      Ok(value_decimal) => {
-        match value_decimal.round_sf_with_strategy(9, RoundingStrategy::MidpointAwayFromZero) {
-          Some(value_decimal) => {
-            if value_decimal == Decimal::ONE {
-              Ok(Some(".999999999".to_string()))
-            } else if value_decimal == Decimal::NEGATIVE_ONE {
-              Ok(Some("-.999999999".to_string()))
-            } else if value_decimal > Decimal::NEGATIVE_ONE && value_decimal < Decimal::ONE {
-              let value_zero_stripped = value_decimal.to_string().replace("0.", ".");
-              Ok(Some(value_zero_stripped))
-            } else {
-              Err(wasm_error!(
-                "Value must be in the range -1..1, but got: `{}`",
-                value_str
-              ))
-            }
-          }
-          None => Err(wasm_error!("Value could not be processed: `{}`", value_str)),
-        }
-      }
-      Err(error) => Err(wasm_error!(
-        "Value could not be processed: `{}`.  Error: `{}`",
-        value_str,
-        error
-      )),
-    },
-    None => Ok(None),
+/// Value string for `value`, and `extra` with the original of a typed value added under
+/// `ORIGINAL_VALUE_EXTRA_KEY`
+pub(crate) fn resolve_value(
+  value: Option<TrustAtomValue>,
+  extra: Option<BTreeMap<String, String>>,
+) -> ExternResult<(Option<String>, Option<BTreeMap<String, String>>)> {
+  let typed = match value {
+    None => return Ok((None, extra)),
+    Some(TrustAtomValue::Raw(value)) => return Ok((Some(value), extra)),
+    Some(typed) => typed,
+  };
+
+  let mut extra = extra.unwrap_or_default();
+  if extra.contains_key(ORIGINAL_VALUE_EXTRA_KEY) {
+    return Err(wasm_error!(
+      "`extra` must not have an `{}` field along with a typed value, which is stored there",
+      ORIGINAL_VALUE_EXTRA_KEY
+    ));
+  }
+  if let Some(original_value) = typed.original_value_json() {
+    extra.insert(ORIGINAL_VALUE_EXTRA_KEY.to_string(), original_value);
   }
+  let value = normalize_value(Some(scaled_value(&typed)?.to_string()))?;
+  Ok((value, Some(extra)))
 }
 
-fn create_link_tag(link_direction: &LinkDirection, chunk_options: &[Option<String>]) -> LinkTag {
-  let mut chunks: Vec<String> = vec![];
-
-  for i in 0..chunk_options.len() {
-    if let Some(chunk) = chunk_options[i].clone() {
-      chunks.push(chunk);
+/// A typed value mapped linearly onto -1..1, from the bottom to the top of its scale
+fn scaled_value(value: &TrustAtomValue) -> ExternResult<Decimal> {
+  match *value {
+    TrustAtomValue::Raw(ref value) => {
+      Decimal::from_str(value).map_err(|_| wasm_error!("Value could not be processed: `{}`", value))
     }
-    if i < chunk_options.len() - 1 {
-      chunks.push(UNICODE_NUL_STR.to_string());
+    TrustAtomValue::Stars { n, max } => {
+      if max < 2 || n < 1 || n > max {
+        return Err(wasm_error!(
+          "Stars must be from 1 to `max` (at least 2), but got: {} of {}",
+          n,
+          max
+        ));
+      }
+      Ok(Decimal::from(n - 1) * Decimal::from(2) / Decimal::from(max - 1) - Decimal::ONE)
+    }
+    TrustAtomValue::Percent { percent } => {
+      if percent > 100 {
+        return Err(wasm_error!(
+          "Percent must be from 0 to 100, but got: {}",
+          percent
+        ));
+      }
+      Ok(Decimal::from(percent) / Decimal::from(50) - Decimal::ONE)
     }
+    TrustAtomValue::Bool(true) => Ok(Decimal::ONE),
+    TrustAtomValue::Bool(false) => Ok(Decimal::NEGATIVE_ONE),
   }
-
-  create_link_tag_metal(link_direction, chunks)
 }
 
-fn create_link_tag_metal(link_direction: &LinkDirection, chunks: Vec<String>) -> LinkTag {
-  let link_tag_arrow = match link_direction {
-    LinkDirection::Forward => LINK_TAG_ARROW_FORWARD,
-    LinkDirection::Reverse => LINK_TAG_ARROW_REVERSE,
-  };
-
-  let mut link_tag_bytes = vec![];
-  link_tag_bytes.extend_from_slice(&LINK_TAG_HEADER);
-  link_tag_bytes.extend_from_slice(&link_tag_arrow);
-
-  for chunk in chunks {
-    link_tag_bytes.extend_from_slice(chunk.as_bytes());
+pub(crate) fn normalize_value(value_str: Option<String>) -> ExternResult<Option<String>> {
+  match value_str {
+    Some(value_str) => Ok(Some(
+      trust_atom_types::value::normalize_value(&value_str)
+        .map_err(|error| wasm_error!("{}", error))?,
+    )),
+    None => Ok(None),
   }
+}
 
-  // debug!("link_tag: {:?}", String::from_utf8_lossy(&link_tag_bytes));
-  LinkTag(link_tag_bytes)
+/// As `normalize_value`, for the range 0..1
+pub(crate) fn normalize_confidence(confidence: Option<String>) -> ExternResult<Option<String>> {
+  match confidence {
+    Some(confidence) => match normalize_value(Some(confidence.clone())) {
+      Ok(Some(normalized)) if !normalized.starts_with('-') => Ok(Some(normalized)),
+      _ => Err(wasm_error!(
+        "Confidence must be in the range 0..1, but got: `{}`",
+        confidence
+      )),
+    },
+    None => Ok(None),
+  }
 }
 
 pub fn get_extra(entry_hash: &EntryHash) -> ExternResult<Extra> {
@@ -177,6 +388,7 @@ fn get_record(entry_hash: &EntryHash, get_options: GetOptions) -> ExternResult<R
 }
 
 pub fn query_mine(
+  link_type: impl LinkTypeFilterExt + Clone,
   target: Option<AnyLinkableHash>,
   content_full: Option<String>,
   content_starts_with: Option<String>,
@@ -185,6 +397,7 @@ pub fn query_mine(
   let agent_address = AnyLinkableHash::from(agent_info()?.agent_initial_pubkey);
 
   let result = query(
+    link_type,
     Some(agent_address),
     target,
     content_full,
@@ -198,26 +411,65 @@ pub fn query_mine(
 /// Required: exactly one of source or target
 /// All other arguments are optional
 /// Arguments act as additive filters (AND)
+/// Links which can't be decoded as trust atoms are skipped
 #[warn(clippy::needless_pass_by_value)]
 pub fn query(
+  link_type: impl LinkTypeFilterExt + Clone,
   source: Option<AnyLinkableHash>,
   target: Option<AnyLinkableHash>,
   content_full: Option<String>,
   content_starts_with: Option<String>,
   value_starts_with: Option<String>,
 ) -> ExternResult<Vec<TrustAtom>> {
-  let (link_direction, link_base) = match (source, target) {
-    (Some(source), None) => (LinkDirection::Forward, source),
-    (None, Some(target)) => (LinkDirection::Reverse, target),
-    (None, None) => return Err(wasm_error!("Either source or target must be specified",)),
-    (Some(_source), Some(_target)) => {
-      return Err(wasm_error!(
-        "Exactly one of source or target must be specified, but not both",
-      ))
-    }
+  let output = query_with_decode_errors(
+    link_type,
+    source,
+    target,
+    content_full,
+    content_starts_with,
+    value_starts_with,
+    false,
+  )?;
+  Ok(output.trust_atoms)
+}
+
+/// Like `query`, but reports the links which can't be decoded as trust atoms (another app's links
+/// under the same link type, or a tag format newer than this zome) in `decode_errors`; with
+/// `strict`, the first such link fails the whole query instead
+#[warn(clippy::needless_pass_by_value)]
+pub fn query_with_decode_errors(
+  link_type: impl LinkTypeFilterExt + Clone,
+  source: Option<AnyLinkableHash>,
+  target: Option<AnyLinkableHash>,
+  content_full: Option<String>,
+  content_starts_with: Option<String>,
+  value_starts_with: Option<String>,
+  strict: bool,
+) -> ExternResult<QueryOutput> {
+  let (direction, link_base) = direction_and_base(source, target)?;
+
+  for (field, filter) in [
+    (TagField::Content, &content_full),
+    (TagField::Content, &content_starts_with),
+    (TagField::Value, &value_starts_with),
+  ] {
+    check_no_nul(field, filter.as_deref().unwrap_or_default())
+      .map_err(|error| wasm_error!("{}", error))?;
+  }
+  // Search for content as `create` stores it
+  let content_full = match content_full {
+    Some(content_full) => Some(normalize_content(&content_full)?),
+    None => None,
   };
+  let content_starts_with = match content_starts_with {
+    Some(content_starts_with) => Some(normalize_content(&content_starts_with)?),
+    None => None,
+  };
+
+  // Compact tags hold the value in binary, so their tag prefix can only narrow by content
+  let compact_value_starts_with = content_full.as_ref().and(value_starts_with.clone());
 
-  let link_tag = match (content_full, content_starts_with, value_starts_with) {
+  let chunks = match (content_full, content_starts_with, value_starts_with) {
     (Some(_content_full), Some(_content_starts_with), _) => {
       return Err(wasm_error!("Only one of `content_full` or `content_starts_with` can be used"))
     },
@@ -226,90 +478,130 @@ pub fn query(
         "Cannot use `value_starts_with` and `content_starts_with` arguments together; maybe try `content_full` instead?",
       ))
     },
-    (Some(content_full), None, Some(value_starts_with)) => Some(create_link_tag(
-      &link_direction,
-      &[Some(content_full), Some(value_starts_with)],
-    )),
-    (Some(content_full), None, None) => {
-      Some(create_link_tag_metal(&link_direction, vec![content_full, UNICODE_NUL_STR.to_string()]))
-    },
-    (None, Some(content_starts_with), None) => Some(create_link_tag(
-      &link_direction,
-      &[Some(content_starts_with)],
-    )),
-    (None, None, Some(value_starts_with)) => Some(create_link_tag(&link_direction, &[Some(value_starts_with)])),
+    (Some(content_full), None, Some(value_starts_with)) => Some(vec![content_full, value_starts_with]),
+    (Some(content_full), None, None) => Some(vec![content_full, String::new()]),
+    (None, Some(content_starts_with), None) => Some(vec![content_starts_with]),
+    (None, None, Some(value_starts_with)) => Some(vec![value_starts_with]),
     (None, None, None) => None,
   };
-  let links = get_links(link_base.clone(), LinkTypes::TrustAtom, link_tag)?;
+  let links = match chunks {
+    None => get_links(link_base.clone(), link_type, None)?,
+    // Older atoms don't have the latest version byte, so search with each version's prefix.  A
+    // version 1 prefix with empty content is also a prefix of every other version's tags, so the
+    // same link can be found more than once.
+    Some(chunks) => {
+      let chunks: Vec<&str> = chunks.iter().map(String::as_str).collect();
+      let mut links = vec![];
+      let mut link_hashes = HashSet::new();
+      for version in TagVersion::ALL {
+        let link_tag = LinkTag::new(encode_prefix(direction, version, &chunks));
+        for link in get_links(link_base.clone(), link_type.clone(), Some(link_tag))? {
+          if link_hashes.insert(link.create_link_hash.clone()) {
+            links.push(link);
+          }
+        }
+      }
+      links
+    }
+  };
 
-  let trust_atoms = convert_links_to_trust_atoms(links, &link_direction, link_base)?;
+  let mut output = convert_links_to_trust_atoms(links, direction, &link_base, strict)?;
+  if let Some(value_starts_with) = compact_value_starts_with {
+    output.trust_atoms.retain(|trust_atom| {
+      trust_atom
+        .value
+        .as_ref()
+        .map_or(false, |value| value.starts_with(&value_starts_with))
+    });
+  }
+
+  Ok(output)
+}
 
-  Ok(trust_atoms)
+/// Link base and direction of the links to read for trust atoms from `source` or about `target`
+fn direction_and_base(
+  source: Option<AnyLinkableHash>,
+  target: Option<AnyLinkableHash>,
+) -> ExternResult<(Direction, AnyLinkableHash)> {
+  match (source, target) {
+    (Some(source), None) => Ok((Direction::Forward, source)),
+    (None, Some(target)) => Ok((Direction::Reverse, target)),
+    (None, None) => Err(wasm_error!("Either source or target must be specified",)),
+    (Some(_source), Some(_target)) => Err(wasm_error!(
+      "Exactly one of source or target must be specified, but not both",
+    )),
+  }
 }
 
-#[allow(clippy::needless_pass_by_value)]
 fn convert_links_to_trust_atoms(
   links: Vec<Link>,
-  link_direction: &LinkDirection,
-  link_base: AnyLinkableHash,
-) -> ExternResult<Vec<TrustAtom>> {
-  let trust_atoms_result: Result<Vec<TrustAtom>, _> = links
-    .into_iter()
-    .map(|link| convert_link_to_trust_atom(link, link_direction, link_base.clone()))
-    .collect();
-  let trust_atoms = trust_atoms_result?;
-  Ok(trust_atoms)
-  // .ok_or_else(|_| wasm_error!("Failure in converting links to trust atoms".to_string())?;
-  //   Ok(trust_atoms.or_else(|_| wasm_error!("hmmmm")?)
+  direction: Direction,
+  link_base: &AnyLinkableHash,
+  strict: bool,
+) -> ExternResult<QueryOutput> {
+  let mut output = QueryOutput::default();
+  for link in links {
+    match decode(&link.tag.0) {
+      Ok(tag) => {
+        output
+          .trust_atoms
+          .push(trust_atom_from_tag(tag, link, direction, link_base.clone()))
+      }
+      Err(error) if strict => {
+        return Err(wasm_error!(
+          "Link {} is not a valid trust atom: {}",
+          link.create_link_hash,
+          error
+        ))
+      }
+      Err(error) => output.decode_errors.push(LinkDecodeError {
+        create_link_hash: link.create_link_hash,
+        error: error.to_string(),
+      }),
+    }
+  }
+  Ok(output)
 }
 
-// #[warn(clippy::pedantic)]
-fn convert_link_to_trust_atom(
+pub(crate) fn convert_link_to_trust_atom(
   link: Link,
-  link_direction: &LinkDirection,
+  direction: Direction,
   link_base: AnyLinkableHash,
 ) -> ExternResult<TrustAtom> {
-  let link_tag_bytes = link.tag.clone().into_inner();
-  let link_tag = match String::from_utf8(link_tag_bytes) {
-    Ok(link_tag) => link_tag,
-    Err(_) => {
-      return Err(wasm_error!(
-        "Link tag is not valid UTF-8 -- found: {:?}",
-        String::from_utf8_lossy(&link.tag.into_inner())
-      ))
-    }
-  };
+  let tag = decode(&link.tag.0).map_err(|error| wasm_error!("{}", error))?;
+  Ok(trust_atom_from_tag(tag, link, direction, link_base))
+}
 
-  let chunks: Vec<&str> = link_tag.split(UNICODE_NUL_STR).collect();
-  let content = chunks[0][tg_link_tag_header_length()..].to_string(); // drop leading "Ŧ→" or "Ŧ↩"
-  let value = chunks[1].to_string();
-
-  let trust_atom = match link_direction {
-    LinkDirection::Forward => {
-      TrustAtom {
-        source_hash: link_base,
-        target_hash: link.target,
-        content: Some(content),
-        value: Some(value),
-        extra: Some(BTreeMap::new()), // TODO
-      }
-    }
-    LinkDirection::Reverse => {
-      TrustAtom {
-        source_hash: link.target, // flipped for Reverse direction
-        target_hash: link_base,   // flipped for Reverse direction
-        content: Some(content),
-        value: Some(value),
-        extra: Some(BTreeMap::new()), // TODO
-      }
-    }
+fn trust_atom_from_tag(
+  tag: TrustAtomTag,
+  link: Link,
+  direction: Direction,
+  link_base: AnyLinkableHash,
+) -> TrustAtom {
+  let (source_hash, target_hash) = match direction {
+    Direction::Forward => (link_base, link.target),
+    Direction::Reverse => (link.target, link_base), // flipped for Reverse direction
   };
-  Ok(trust_atom)
+  TrustAtom {
+    source_hash,
+    target_hash,
+    content: Some(tag.content.unwrap_or_default()),
+    display_content: tag.display_content,
+    value: Some(tag.value.unwrap_or_default()),
+    confidence: tag.confidence,
+    extra: Some(BTreeMap::new()), // TODO
+  }
 }
 
-const fn tg_link_tag_header_length() -> usize {
-  // leaving this nomenclature for now
-  LINK_TAG_HEADER.len() + LINK_TAG_ARROW_FORWARD.len()
+/// Hash of the `Extra` entry referenced by a trust atom link tag, if any
+pub(crate) fn extra_hash_from_link_tag(link_tag: &LinkTag) -> ExternResult<Option<EntryHash>> {
+  let tag = decode(&link_tag.0).map_err(|error| wasm_error!("{}", error))?;
+  match tag.extra_hash {
+    Some(extra_hash) => EntryHashB64::from_b64_str(&extra_hash)
+      .map(|extra_hash| Some(extra_hash.into()))
+      .map_err(|_| wasm_error!("Invalid extra entry hash in link tag: `{}`", extra_hash)),
+    None => Ok(None),
+  }
 }
 
 #[cfg(test)]
@@ -441,10 +733,150 @@ mod tests {
     }
   }
 
+  #[test]
+  fn test_normalize_confidence() {
+    assert_eq!(normalize_confidence(None).unwrap(), None);
+    assert_eq!(
+      normalize_confidence(Some("0.5".to_string())).unwrap(),
+      Some(".500000000".to_string())
+    );
+    assert_eq!(
+      normalize_confidence(Some("1".to_string())).unwrap(),
+      Some(".999999999".to_string())
+    );
+    assert_eq!(
+      normalize_confidence(Some("0".to_string())).unwrap(),
+      Some("0".to_string())
+    );
+
+    for confidence in ["-.1", "-1", "1.1", "sure"] {
+      let error_message = normalize_confidence(Some(confidence.to_string()))
+        .expect_err(&format!(
+          "expected error for confidence `{confidence}`, got"
+        ))
+        .to_string();
+      assert!(
+        error_message.contains("Confidence must be in the range 0..1"),
+        "{error_message}"
+      );
+    }
+  }
+
+  #[test]
+  fn test_scaled_value() {
+    let values_and_expected = [
+      (TrustAtomValue::Stars { n: 1, max: 5 }, "-.999999999"),
+      (TrustAtomValue::Stars { n: 3, max: 5 }, "0"),
+      (TrustAtomValue::Stars { n: 4, max: 5 }, ".500000000"),
+      (TrustAtomValue::Stars { n: 5, max: 5 }, ".999999999"),
+      (TrustAtomValue::Stars { n: 2, max: 4 }, "-.333333333"),
+      (TrustAtomValue::Stars { n: 1, max: 2 }, "-.999999999"),
+      (TrustAtomValue::Percent { percent: 0 }, "-.999999999"),
+      (TrustAtomValue::Percent { percent: 50 }, "0"),
+      (TrustAtomValue::Percent { percent: 80 }, ".600000000"),
+      (TrustAtomValue::Percent { percent: 100 }, ".999999999"),
+      (TrustAtomValue::Bool(true), ".999999999"),
+      (TrustAtomValue::Bool(false), "-.999999999"),
+      (TrustAtomValue::from("0.8"), ".800000000"),
+    ];
+
+    for (value, expected) in values_and_expected {
+      let scaled = scaled_value(&value).unwrap().to_string();
+      let normalized = normalize_value(Some(scaled)).unwrap().unwrap();
+      assert_eq!(normalized, expected, "{value:?}");
+    }
+  }
+
+  #[test]
+  fn test_scaled_value__out_of_range() {
+    for value in [
+      TrustAtomValue::Stars { n: 0, max: 5 },
+      TrustAtomValue::Stars { n: 6, max: 5 },
+      TrustAtomValue::Stars { n: 1, max: 1 },
+      TrustAtomValue::Percent { percent: 101 },
+    ] {
+      assert!(scaled_value(&value).is_err(), "{value:?}");
+    }
+  }
+
+  #[test]
+  fn test_resolve_value() {
+    let (value, extra) = resolve_value(Some(TrustAtomValue::from("0.8")), None).unwrap();
+    assert_eq!(value, Some("0.8".to_string()));
+    assert_eq!(extra, None);
+
+    let details = BTreeMap::from([("details".to_string(), "Great rolls".to_string())]);
+    let (value, extra) = resolve_value(
+      Some(TrustAtomValue::Stars { n: 4, max: 5 }),
+      Some(details.clone()),
+    )
+    .unwrap();
+    assert_eq!(value, Some(".500000000".to_string()));
+    let extra = extra.unwrap();
+    assert_eq!(extra["details"], details["details"]);
+    assert_eq!(extra[ORIGINAL_VALUE_EXTRA_KEY], r#"{"n":4,"max":5}"#);
+
+    let clashing = BTreeMap::from([(ORIGINAL_VALUE_EXTRA_KEY.to_string(), "5 stars".to_string())]);
+    assert!(resolve_value(Some(TrustAtomValue::Bool(true)), Some(clashing)).is_err());
+  }
+
   #[test]
   fn test_bucket_val() {
     let bytes: [u8; 9] = [9, 10, 11, 12, 13, 14, 15, 16, 17];
     let bucket = create_bucket_string(&bytes);
     assert_eq!(bucket, "901234567".to_string());
   }
+
+  fn link_with_tag(tag: Vec<u8>, byte: u8) -> Link {
+    Link {
+      target: AnyLinkableHash::from(EntryHash::from_raw_36(vec![byte; 36])),
+      timestamp: Timestamp::from_micros(1_678_312_691_145_941),
+      tag: LinkTag::new(tag),
+      create_link_hash: ActionHash::from_raw_36(vec![byte; 36]),
+    }
+  }
+
+  fn mixed_links() -> Vec<Link> {
+    let valid_tag = [
+      build_forward_header(),
+      vec![2],
+      "sushi\0.8".as_bytes().to_vec(),
+    ]
+    .concat();
+    vec![
+      link_with_tag(valid_tag, 1),
+      link_with_tag("not a trust atom".as_bytes().to_vec(), 2),
+      link_with_tag(build_forward_header()[0..3].to_vec(), 3),
+      link_with_tag([build_forward_header(), vec![0x1f]].concat(), 4),
+    ]
+  }
+
+  #[test]
+  fn test_convert_links_to_trust_atoms__skips_bad_links() {
+    let link_base = AnyLinkableHash::from(AgentPubKey::from_raw_36(vec![7; 36]));
+    let output =
+      convert_links_to_trust_atoms(mixed_links(), Direction::Forward, &link_base, false).unwrap();
+
+    assert_eq!(output.trust_atoms.len(), 1);
+    assert_eq!(output.trust_atoms[0].content, Some("sushi".to_string()));
+    assert_eq!(output.trust_atoms[0].value, Some(".8".to_string()));
+
+    let bad_link_hashes: Vec<ActionHash> = output
+      .decode_errors
+      .iter()
+      .map(|decode_error| decode_error.create_link_hash.clone())
+      .collect();
+    assert_eq!(
+      bad_link_hashes,
+      [2, 3, 4].map(|byte| ActionHash::from_raw_36(vec![byte; 36]))
+    );
+    assert!(output.decode_errors[2].error.contains("version 31"));
+  }
+
+  #[test]
+  fn test_convert_links_to_trust_atoms__strict() {
+    let link_base = AnyLinkableHash::from(AgentPubKey::from_raw_36(vec![7; 36]));
+    let result = convert_links_to_trust_atoms(mixed_links(), Direction::Forward, &link_base, true);
+    assert!(result.is_err());
+  }
 }
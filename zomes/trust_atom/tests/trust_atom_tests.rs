@@ -2,12 +2,14 @@
 
 use futures::future;
 use std::collections::BTreeMap;
+use trust_atom_types::value::{TrustAtomValue, ORIGINAL_VALUE_EXTRA_KEY};
 use trust_atom_types::DeleteReport;
 
 use hdk::prelude::*;
 use holochain::sweettest::{
   SweetAgents, SweetAppBatch, SweetCell, SweetConductor, SweetConductorBatch, SweetDnaFile,
 };
+use holochain::test_utils::consistency_10s;
 
 const DNA_FILEPATH: &str = "../../workdir/dna/trust_atom_dna.dna";
 
@@ -29,7 +31,7 @@ pub async fn test_create_trust_atom() {
     .call(
       &cell1.zome("trust_atom"),
       "create_string_target",
-      "Nuka Sushi",
+      string_target("Nuka Sushi"),
     )
     .await;
 
@@ -46,7 +48,8 @@ pub async fn test_create_trust_atom() {
   let trust_atom_input = trust_atom_types::TrustAtomInput {
     target: AnyLinkableHash::from(target_hash.clone()),
     content: Some(content.clone()),
-    value: Some(value.clone()),
+    value: Some(value.clone().into()),
+    confidence: None,
     extra: Some(extra.clone()),
   };
 
@@ -82,7 +85,7 @@ pub async fn test_create_trust_atom() {
 
   let chunks: Vec<&str> = relevant_link_string.split(unicode_nul).collect();
   assert_eq!(chunks.len(), 4);
-  assert_eq!(chunks[0], "Ŧ→sushi");
+  assert_eq!(chunks[0], "Ŧ→\u{2}sushi");
   assert_eq!(chunks[1], ".800000000");
 
   let bucket = chunks[2];
@@ -94,7 +97,7 @@ pub async fn test_create_trust_atom() {
   let expected_link_tag_string = format!(
     "{}{}{}{}{}{}{}{}{}",
     "Ŧ",
-    "→",
+    "→\u{2}",
     "sushi",
     unicode_nul,
     ".800000000",
@@ -127,7 +130,7 @@ pub async fn test_create_trust_atom() {
   let expected_link_tag_string = format!(
     "{}{}{}{}{}{}{}{}{}",
     "Ŧ",
-    "↩",
+    "↩\u{2}",
     "sushi",
     unicode_nul,
     ".800000000",
@@ -140,7 +143,7 @@ pub async fn test_create_trust_atom() {
 
   let chunks: Vec<&str> = relevant_link_string.split(unicode_nul).collect();
   assert_eq!(chunks.len(), 4);
-  assert_eq!(chunks[0], "Ŧ↩sushi");
+  assert_eq!(chunks[0], "Ŧ↩\u{2}sushi");
   assert_eq!(chunks[1], ".800000000");
   assert_eq!(chunks[2], bucket);
   assert_eq!(chunks[3], expected_entry_hash);
@@ -157,7 +160,7 @@ pub async fn test_create_trust_atom_with_empty_chunks() {
     .call(
       &cell1.zome("trust_atom"),
       "create_string_target",
-      "Nuka Sushi",
+      string_target("Nuka Sushi"),
     )
     .await;
 
@@ -167,6 +170,7 @@ pub async fn test_create_trust_atom_with_empty_chunks() {
     target: AnyLinkableHash::from(target_hash.clone()),
     content: None,
     value: None,
+    confidence: None,
     extra: None,
   };
 
@@ -202,7 +206,7 @@ pub async fn test_create_trust_atom_with_empty_chunks() {
 
   let chunks: Vec<&str> = relevant_link_string.split(unicode_nul).collect();
   assert_eq!(chunks.len(), 4);
-  assert_eq!(chunks[0], "Ŧ→");
+  assert_eq!(chunks[0], "Ŧ→\u{2}");
   assert_eq!(chunks[1], "");
 
   let bucket = chunks[2];
@@ -212,7 +216,7 @@ pub async fn test_create_trust_atom_with_empty_chunks() {
 
   let expected_link_tag_string = format!(
     "{}{}{}{}{}{}",
-    "Ŧ", "→", unicode_nul, unicode_nul, bucket, unicode_nul
+    "Ŧ", "→\u{2}", unicode_nul, unicode_nul, bucket, unicode_nul
   );
   assert_eq!(relevant_link_string, expected_link_tag_string);
 
@@ -234,13 +238,13 @@ pub async fn test_create_trust_atom_with_empty_chunks() {
   let relevant_link_string = String::from_utf8(relevant_link_bytes).unwrap();
   let expected_link_tag_string = format!(
     "{}{}{}{}{}{}",
-    "Ŧ", "↩", unicode_nul, unicode_nul, bucket, unicode_nul
+    "Ŧ", "↩\u{2}", unicode_nul, unicode_nul, bucket, unicode_nul
   );
   assert_eq!(relevant_link_string, expected_link_tag_string);
 
   let chunks: Vec<&str> = relevant_link_string.split(unicode_nul).collect();
   assert_eq!(chunks.len(), 4);
-  assert_eq!(chunks[0], "Ŧ↩");
+  assert_eq!(chunks[0], "Ŧ↩\u{2}");
   assert_eq!(chunks[1], "");
   assert_eq!(chunks[2], bucket);
 }
@@ -254,7 +258,7 @@ pub async fn test_delete_trust_atom() {
     .call(
       &cell1.zome("trust_atom"),
       "create_string_target",
-      "Nuka Sushi",
+      string_target("Nuka Sushi"),
     )
     .await;
 
@@ -269,7 +273,8 @@ pub async fn test_delete_trust_atom() {
   let trust_atom_input = trust_atom_types::TrustAtomInput {
     target: target.clone(),
     content: Some(content.clone()),
-    value: Some(value.clone()),
+    value: Some(value.clone().into()),
+    confidence: None,
     extra: Some(extra.clone()),
   };
 
@@ -293,7 +298,7 @@ pub async fn test_delete_trust_atom() {
 
   // SANITY CHECK: 2 "FORWARD" TRUST ATOMS EXIST
 
-  let trust_atom_links: Vec<trust_atom_types::TrustAtom> = conductor
+  let query_output: trust_atom_types::QueryOutput = conductor
     .call(
       &cell1.zome("trust_atom"),
       "query",
@@ -303,15 +308,17 @@ pub async fn test_delete_trust_atom() {
         content_full: None,
         content_starts_with: None,
         value_starts_with: None,
+        strict: None,
       },
     )
     .await;
+  let trust_atom_links = query_output.trust_atoms;
 
   assert_eq!(trust_atom_links.len(), 2);
 
   // SANITY CHECK: 2 "BACKWARD" TRUST ATOMS EXIST
 
-  let trust_atom_links: Vec<trust_atom_types::TrustAtom> = conductor
+  let query_output: trust_atom_types::QueryOutput = conductor
     .call(
       &cell1.zome("trust_atom"),
       "query",
@@ -321,9 +328,11 @@ pub async fn test_delete_trust_atom() {
         content_full: None,
         content_starts_with: None,
         value_starts_with: None,
+        strict: None,
       },
     )
     .await;
+  let trust_atom_links = query_output.trust_atoms;
 
   assert_eq!(trust_atom_links.len(), 2);
 
@@ -343,7 +352,7 @@ pub async fn test_delete_trust_atom() {
 
   // SHOULD BE ZERO "FORWARD" TRUST ATOMS
 
-  let trust_atom_links: Vec<trust_atom_types::TrustAtom> = conductor
+  let query_output: trust_atom_types::QueryOutput = conductor
     .call(
       &cell1.zome("trust_atom"),
       "query",
@@ -353,15 +362,17 @@ pub async fn test_delete_trust_atom() {
         content_full: None,
         content_starts_with: None,
         value_starts_with: None,
+        strict: None,
       },
     )
     .await;
+  let trust_atom_links = query_output.trust_atoms;
 
   assert_eq!(trust_atom_links.len(), 0);
 
   // SHOULD BE ZERO "BACKWARD" TRUST ATOMS
 
-  let trust_atom_links: Vec<trust_atom_types::TrustAtom> = conductor
+  let query_output: trust_atom_types::QueryOutput = conductor
     .call(
       &cell1.zome("trust_atom"),
       "query",
@@ -371,9 +382,11 @@ pub async fn test_delete_trust_atom() {
         content_full: None,
         content_starts_with: None,
         value_starts_with: None,
+        strict: None,
       },
     )
     .await;
+  let trust_atom_links = query_output.trust_atoms;
 
   assert_eq!(trust_atom_links.len(), 0);
 }
@@ -389,7 +402,7 @@ pub async fn test_query_mine() {
     .call(
       &cell1.zome("trust_atom"),
       "create_string_target",
-      "Sushi Ran",
+      string_target("Sushi Ran"),
     )
     .await;
 
@@ -402,7 +415,8 @@ pub async fn test_query_mine() {
       trust_atom_types::TrustAtomInput {
         target: AnyLinkableHash::from(target_hash.clone()),
         content: Some("sushi".to_string()),
-        value: Some("0.8".to_string()),
+        value: Some("0.8".into()),
+        confidence: None,
         extra: Some(BTreeMap::new()),
         // extra: Some(BTreeMap::new([
         //   ("creator_name".into(), "Bradley Fieldstone Jr.".into()),
@@ -413,7 +427,7 @@ pub async fn test_query_mine() {
 
   // QUERY MY TRUST ATOMS
 
-  let trust_atoms_from_query: Vec<trust_atom_types::TrustAtom> = conductor
+  let query_output: trust_atom_types::QueryOutput = conductor
     .call(
       &cell1.zome("trust_atom"),
       "query_mine",
@@ -422,9 +436,11 @@ pub async fn test_query_mine() {
         content_starts_with: None,
         content_full: None,
         value_starts_with: None,
+        strict: None,
       },
     )
     .await;
+  let trust_atoms_from_query = query_output.trust_atoms;
 
   assert_eq!(trust_atoms_from_query.len(), 1);
 
@@ -438,6 +454,8 @@ pub async fn test_query_mine() {
       source_hash: AnyLinkableHash::from(agent.clone()),
       target_hash: AnyLinkableHash::from(target_hash),
       content: Some("sushi".to_string()),
+      display_content: None,
+      confidence: None,
       value: Some(".800000000".to_string()),
       extra: Some(BTreeMap::new()),
     }
@@ -454,7 +472,7 @@ pub async fn test_query_mine_with_content_starts_with() {
     .call(
       &cell1.zome("trust_atom"),
       "create_string_target",
-      "Sushi Ran",
+      string_target("Sushi Ran"),
     )
     .await;
 
@@ -471,6 +489,7 @@ pub async fn test_query_mine_with_content_starts_with() {
           target: AnyLinkableHash::from(target_hash.clone()),
           content: Some(content.into()),
           value: Some("0.8".into()),
+          confidence: None,
           extra: Some(BTreeMap::new()),
         },
       )
@@ -478,7 +497,7 @@ pub async fn test_query_mine_with_content_starts_with() {
   }
   // QUERY MY TRUST ATOMS
 
-  let trust_atoms_from_query: Vec<trust_atom_types::TrustAtom> = conductor
+  let query_output: trust_atom_types::QueryOutput = conductor
     .call(
       &cell1.zome("trust_atom"),
       "query_mine",
@@ -487,10 +506,12 @@ pub async fn test_query_mine_with_content_starts_with() {
         content_full: None,
         content_starts_with: Some("sushi".into()),
         value_starts_with: None,
+        strict: None,
         // value_starts_with: Some("0.0".into()),
       },
     )
     .await;
+  let trust_atoms_from_query = query_output.trust_atoms;
 
   assert_eq!(trust_atoms_from_query.len(), 2);
 
@@ -517,7 +538,7 @@ pub async fn test_query_mine_with_content_full() {
     .call(
       &cell1.zome("trust_atom"),
       "create_string_target",
-      "Sushi Ran",
+      string_target("Sushi Ran"),
     )
     .await;
 
@@ -534,6 +555,7 @@ pub async fn test_query_mine_with_content_full() {
           target: AnyLinkableHash::from(target_hash.clone()),
           content: Some(content_full.into()),
           value: Some("0.8".into()),
+          confidence: None,
           extra: Some(BTreeMap::new()),
         },
       )
@@ -541,7 +563,7 @@ pub async fn test_query_mine_with_content_full() {
   }
   // QUERY MY TRUST ATOMS
 
-  let trust_atoms_from_query: Vec<trust_atom_types::TrustAtom> = conductor
+  let query_output: trust_atom_types::QueryOutput = conductor
     .call(
       &cell1.zome("trust_atom"),
       "query_mine",
@@ -550,10 +572,12 @@ pub async fn test_query_mine_with_content_full() {
         content_full: Some("sushi".into()),
         content_starts_with: None,
         value_starts_with: None,
+        strict: None,
         // value_starts_with: Some("0.0".into()),
       },
     )
     .await;
+  let trust_atoms_from_query = query_output.trust_atoms;
 
   assert_eq!(trust_atoms_from_query.len(), 1);
 
@@ -572,14 +596,15 @@ pub async fn test_get_extra() {
     .call(
       &cell1.zome("trust_atom"),
       "create_string_target",
-      "Nuka Sushi",
+      string_target("Nuka Sushi"),
     )
     .await;
 
   let mock_input = trust_atom_types::TrustAtomInput {
     target: target_hash,
     content: Some("sushi".to_string()),
-    value: Some("0.9871".to_string()),
+    value: Some("0.9871".into()),
+    confidence: None,
     extra: Some(BTreeMap::from([
       (
         "extra_stuff".to_string(),
@@ -663,41 +688,1517 @@ pub async fn test_get_entry_by_actionhash() {
   assert_eq!("test".to_string(), retrieval.example_field);
 }
 
-// #[tokio::test(flavor = "multi_thread")]
-// pub async fn test_fetch_external() {
-//   let (conductor, agent, cell1): (SweetConductor, AgentPubKey, SweetCell) = setup_1_conductor().await;
+#[tokio::test(flavor = "multi_thread")]
+pub async fn test_cached_rollup_staleness() {
+  let (conductor, agent, cell1): (SweetConductor, AgentPubKey, SweetCell) =
+    setup_1_conductor().await;
+
+  let target_hash: EntryHash = conductor
+    .call(
+      &cell1.zome("trust_atom"),
+      "create_string_target",
+      string_target("Nuka Sushi"),
+    )
+    .await;
+
+  let _result: trust_atom_types::TrustAtom = conductor
+    .call(
+      &cell1.zome("trust_atom"),
+      "create_trust_atom",
+      trust_atom_types::TrustAtomInput {
+        target: AnyLinkableHash::from(target_hash.clone()),
+        content: Some("sushi".into()),
+        value: Some("0.8".into()),
+        confidence: None,
+        extra: None,
+      },
+    )
+    .await;
+
+  let rollup_input = trust_atom_types::RollupInput {
+    seed: None,
+    content_full: None,
+    content_starts_with: Some("sushi".into()),
+    weighting: None,
+  };
+
+  // NOTHING CACHED YET
+
+  let cached: trust_atom_types::CachedRollupOutput = conductor
+    .call(
+      &cell1.zome("trust_atom"),
+      "get_cached_rollup",
+      trust_atom_types::GetCachedRollupInput {
+        rollup: rollup_input.clone(),
+        refresh_if_stale: false,
+        private: false,
+      },
+    )
+    .await;
+
+  assert!(cached.rollup.is_none());
+  assert!(cached.stale);
+
+  // CACHE ROLLUP
+
+  let cached: trust_atom_types::CachedRollupOutput = conductor
+    .call(
+      &cell1.zome("trust_atom"),
+      "cache_rollup",
+      trust_atom_types::CacheRollupInput {
+        rollup: rollup_input.clone(),
+        private: true,
+      },
+    )
+    .await;
+
+  assert_eq!(
+    cached.rollup,
+    Some(trust_atom_types::Rollup {
+      seed: AnyLinkableHash::from(agent.clone()),
+      scores: vec![trust_atom_types::RollupScore {
+        target_hash: AnyLinkableHash::from(target_hash.clone()),
+        value: ".800000000".into(),
+        raters: 1,
+      }],
+    })
+  );
+
+  let cached: trust_atom_types::CachedRollupOutput = conductor
+    .call(
+      &cell1.zome("trust_atom"),
+      "get_cached_rollup",
+      trust_atom_types::GetCachedRollupInput {
+        rollup: rollup_input.clone(),
+        refresh_if_stale: false,
+        private: true,
+      },
+    )
+    .await;
+
+  assert!(!cached.stale);
+  assert_eq!(cached.rollup.unwrap().scores.len(), 1);
+
+  // CHANGE FIRST HOP
+
+  let other_target_hash: EntryHash = conductor
+    .call(
+      &cell1.zome("trust_atom"),
+      "create_string_target",
+      string_target("Sushi Ran"),
+    )
+    .await;
+
+  let _result: trust_atom_types::TrustAtom = conductor
+    .call(
+      &cell1.zome("trust_atom"),
+      "create_trust_atom",
+      trust_atom_types::TrustAtomInput {
+        target: AnyLinkableHash::from(other_target_hash),
+        content: Some("sushi".into()),
+        value: Some("0.9".into()),
+        confidence: None,
+        extra: None,
+      },
+    )
+    .await;
+
+  let cached: trust_atom_types::CachedRollupOutput = conductor
+    .call(
+      &cell1.zome("trust_atom"),
+      "get_cached_rollup",
+      trust_atom_types::GetCachedRollupInput {
+        rollup: rollup_input.clone(),
+        refresh_if_stale: false,
+        private: true,
+      },
+    )
+    .await;
+
+  assert!(cached.stale);
+  assert_eq!(cached.rollup.unwrap().scores.len(), 1);
+
+  // REFRESH
+
+  let cached: trust_atom_types::CachedRollupOutput = conductor
+    .call(
+      &cell1.zome("trust_atom"),
+      "get_cached_rollup",
+      trust_atom_types::GetCachedRollupInput {
+        rollup: rollup_input,
+        refresh_if_stale: true,
+        private: true,
+      },
+    )
+    .await;
+
+  assert!(!cached.stale);
+  let scores = cached.rollup.unwrap().scores;
+  assert_eq!(scores.len(), 2);
+  assert_eq!(scores[0].value, ".900000000");
+  assert_eq!(scores[1].value, ".800000000");
+}
+
+#[tokio::test(flavor = "multi_thread")]
+pub async fn test_summarize_target_weighting() {
+  let (conductors, _agents, apps) = setup_conductors(2).await;
+  let cells = apps.cells_flattened();
+  let (conductor_me, conductor_stranger) = (&conductors[0], &conductors[1]);
+  let (cell_me, cell_stranger) = (&cells[0], &cells[1]);
+
+  let target_hash: EntryHash = conductor_me
+    .call(
+      &cell_me.zome("trust_atom"),
+      "create_string_target",
+      string_target("Nuka Sushi"),
+    )
+    .await;
+  let target = AnyLinkableHash::from(target_hash);
+
+  // I RATE IT HIGHLY, A STRANGER (NO PATH FROM ME) RATES IT LOWLY
+
+  let _result: trust_atom_types::TrustAtom = conductor_me
+    .call(
+      &cell_me.zome("trust_atom"),
+      "create_trust_atom",
+      trust_atom_types::TrustAtomInput {
+        target: target.clone(),
+        content: Some("sushi".into()),
+        value: Some("0.8".into()),
+        confidence: None,
+        extra: None,
+      },
+    )
+    .await;
+
+  let _result: trust_atom_types::TrustAtom = conductor_stranger
+    .call(
+      &cell_stranger.zome("trust_atom"),
+      "create_trust_atom",
+      trust_atom_types::TrustAtomInput {
+        target: target.clone(),
+        content: Some("sushi".into()),
+        value: Some("-0.4".into()),
+        confidence: None,
+        extra: None,
+      },
+    )
+    .await;
+
+  consistency_10s([cell_me, cell_stranger]).await;
+
+  let summarize = |weighting| trust_atom_types::SummarizeTargetInput {
+    target: target.clone(),
+    content_full: Some("sushi".into()),
+    content_starts_with: None,
+    weighting,
+  };
+
+  let summary: trust_atom_types::TargetSummary = conductor_me
+    .call(
+      &cell_me.zome("trust_atom"),
+      "summarize_target",
+      summarize(None),
+    )
+    .await;
+  assert_eq!(summary.value, Some(".200000000".into()));
+  assert_eq!(summary.ratings_counted, 2);
+
+  let summary: trust_atom_types::TargetSummary = conductor_me
+    .call(
+      &cell_me.zome("trust_atom"),
+      "summarize_target",
+      summarize(Some(trust_atom_types::RaterWeighting::IncomingTrust)),
+    )
+    .await;
+  assert_eq!(summary.value, Some(".788118812".into()));
+  assert_eq!(summary.ratings_counted, 2);
+
+  let summary: trust_atom_types::TargetSummary = conductor_me
+    .call(
+      &cell_me.zome("trust_atom"),
+      "summarize_target",
+      summarize(Some(trust_atom_types::RaterWeighting::RequirePath)),
+    )
+    .await;
+  assert_eq!(
+    summary,
+    trust_atom_types::TargetSummary {
+      target_hash: target.clone(),
+      value: Some(".800000000".into()),
+      ratings_counted: 1,
+      ratings_dropped: 1,
+    }
+  );
+}
+
+#[tokio::test(flavor = "multi_thread")]
+pub async fn test_similar_agents() {
+  let (conductors, agents, apps) = setup_conductors(3).await;
+  let cells = apps.cells_flattened();
+
+  let mut targets: Vec<AnyLinkableHash> = vec![];
+  for name in ["Nuka Sushi", "Sushi Ran", "Pizza Pie"] {
+    let target_hash: EntryHash = conductors[0]
+      .call(
+        &cells[0].zome("trust_atom"),
+        "create_string_target",
+        string_target(name),
+      )
+      .await;
+    targets.push(AnyLinkableHash::from(target_hash));
+  }
+
+  // AGENT 1 AGREES WITH ME, AGENT 2 DISAGREES
+
+  let ratings = [
+    [".9", ".1", "-.5"], // me
+    [".8", ".2", "-.4"], // agent 1
+    ["-.9", ".5", ".8"], // agent 2
+  ];
+  for (i, agent_ratings) in ratings.iter().enumerate() {
+    for (target, value) in targets.iter().zip(agent_ratings) {
+      let _result: trust_atom_types::TrustAtom = conductors[i]
+        .call(
+          &cells[i].zome("trust_atom"),
+          "create_trust_atom",
+          trust_atom_types::TrustAtomInput {
+            target: target.clone(),
+            content: Some("food".into()),
+            value: Some(value.into()),
+            confidence: None,
+            extra: None,
+          },
+        )
+        .await;
+    }
+  }
+
+  consistency_10s(cells.iter()).await;
+
+  let similarity: trust_atom_types::AgentSimilarity = conductors[0]
+    .call(
+      &cells[0].zome("trust_atom"),
+      "agent_similarity",
+      trust_atom_types::AgentSimilarityInput {
+        agent: AnyLinkableHash::from(agents[1].clone()),
+        compared_to: None,
+        metric: trust_atom_types::SimilarityMetric::Pearson,
+      },
+    )
+    .await;
+  assert_eq!(similarity.shared_ratings, 3);
+  assert!(similarity.similarity.unwrap() > 0.9);
+
+  let similar: Vec<trust_atom_types::AgentSimilarity> = conductors[0]
+    .call(
+      &cells[0].zome("trust_atom"),
+      "similar_agents",
+      trust_atom_types::SimilarAgentsInput {
+        metric: trust_atom_types::SimilarityMetric::Cosine,
+        limit: 10,
+        radius: 1,
+        min_shared_ratings: Some(2),
+      },
+    )
+    .await;
+
+  assert_eq!(similar.len(), 2);
+  assert_eq!(similar[0].agent, AnyLinkableHash::from(agents[1].clone()));
+  assert_eq!(similar[1].agent, AnyLinkableHash::from(agents[2].clone()));
+  assert!(similar[1].similarity.unwrap() < 0.0);
+}
+
+#[tokio::test(flavor = "multi_thread")]
+pub async fn test_recommend() {
+  let (conductors, agents, apps) = setup_conductors(2).await;
+  let cells = apps.cells_flattened();
+
+  let mut targets: Vec<AnyLinkableHash> = vec![];
+  for name in ["Nuka Sushi", "Sushi Ran", "Sushi Gone Wrong"] {
+    let target_hash: EntryHash = conductors[0]
+      .call(
+        &cells[0].zome("trust_atom"),
+        "create_string_target",
+        string_target(name),
+      )
+      .await;
+    targets.push(AnyLinkableHash::from(target_hash));
+  }
+
+  let create = |i: usize, target: AnyLinkableHash, content: &str, value: &str| {
+    let input = trust_atom_types::TrustAtomInput {
+      target,
+      content: Some(content.into()),
+      value: Some(value.into()),
+      confidence: None,
+      extra: None,
+    };
+    let (conductor, cell) = (&conductors[i], &cells[i]);
+    async move {
+      let _result: trust_atom_types::TrustAtom = conductor
+        .call(&cell.zome("trust_atom"), "create_trust_atom", input)
+        .await;
+    }
+  };
+
+  // I TRUST AGENT 1, AND HAVE ALREADY RATED "SUSHI RAN"; MY RATINGS OF RESTAURANTS DON'T MAKE THEM
+  // RECOMMENDERS, OR DILUTE AGENT 1'S RATINGS
+
+  create(0, AnyLinkableHash::from(agents[1].clone()), "foodie", ".9").await;
+  create(0, targets[1].clone(), "sushi", ".5").await;
+  create(0, targets[1].clone(), "decor", ".9").await;
+  create(0, targets[2].clone(), "decor", ".7").await;
+
+  create(1, targets[0].clone(), "sushi", ".8").await;
+  create(1, targets[1].clone(), "sushi", ".6").await;
+  create(1, targets[2].clone(), "sushi", "-.7").await;
+
+  consistency_10s(cells.iter()).await;
+
+  let recommendations: Vec<trust_atom_types::Recommendation> = conductors[0]
+    .call(
+      &cells[0].zome("trust_atom"),
+      "recommend",
+      trust_atom_types::RecommendInput {
+        content: "sushi".into(),
+        limit: 10,
+        via: None,
+      },
+    )
+    .await;
+
+  assert_eq!(recommendations.len(), 1);
+  assert_eq!(recommendations[0].target_hash, targets[0]);
+  assert_eq!(recommendations[0].score, ".800000000");
+  assert_eq!(recommendations[0].provenance.len(), 1);
+  assert_eq!(
+    recommendations[0].provenance[0].source_hash,
+    AnyLinkableHash::from(agents[1].clone())
+  );
+}
+
+#[tokio::test(flavor = "multi_thread")]
+pub async fn test_export_graph() {
+  let (conductor, agent, cell1): (SweetConductor, AgentPubKey, SweetCell) =
+    setup_1_conductor().await;
+
+  let target_hash: EntryHash = conductor
+    .call(
+      &cell1.zome("trust_atom"),
+      "create_string_target",
+      string_target("Nuka Sushi"),
+    )
+    .await;
+
+  let _result: trust_atom_types::TrustAtom = conductor
+    .call(
+      &cell1.zome("trust_atom"),
+      "create_trust_atom",
+      trust_atom_types::TrustAtomInput {
+        target: AnyLinkableHash::from(target_hash.clone()),
+        content: Some("sushi".into()),
+        value: Some("0.8".into()),
+        confidence: None,
+        extra: None,
+      },
+    )
+    .await;
+
+  let dot: String = conductor
+    .call(
+      &cell1.zome("trust_atom"),
+      "export_graph",
+      trust_atom_types::ExportGraphInput {
+        seed: None,
+        depth: 0,
+        format: trust_atom_types::graph_export::GraphFormat::Dot,
+      },
+    )
+    .await;
+
+  assert!(dot.contains(&format!(
+    "\"{}\" -> \"{}\" [label=\"sushi .800000000\"",
+    AnyLinkableHash::from(agent),
+    AnyLinkableHash::from(target_hash)
+  )));
+}
+
+#[tokio::test(flavor = "multi_thread")]
+pub async fn test_create_trust_atoms() {
+  let (conductor, _agent, cell1): (SweetConductor, AgentPubKey, SweetCell) =
+    setup_1_conductor().await;
+
+  let csv = "target,content,value,extra\n\
+             https://nukasushi.example,sushi,0.8,\"{\"\"details\"\": \"\"Excellent specials\"\"}\"\n\
+             https://sushiya.example,sushi,1.5,\n\
+             https://ramen.example,ramen,-0.2,\n";
+  let inputs: Vec<trust_atom_types::TrustAtomInput> = trust_atom_types::import::parse_csv(csv)
+    .into_iter()
+    .map(Result::unwrap)
+    .collect();
+
+  let results: Vec<trust_atom_types::CreateTrustAtomResult> = conductor
+    .call(&cell1.zome("trust_atom"), "create_trust_atoms", inputs)
+    .await;
+
+  assert_eq!(results.len(), 3);
+  assert!(results[0].error.is_none());
+  assert_eq!(
+    results[0].trust_atom.clone().unwrap().extra,
+    Some(BTreeMap::from([(
+      "details".to_string(),
+      "Excellent specials".to_string()
+    )]))
+  );
+  assert!(results[1].trust_atom.is_none());
+  assert!(results[1]
+    .error
+    .clone()
+    .unwrap()
+    .contains("Value must be in the range -1..1"));
+  assert!(results[2].error.is_none());
+
+  let query_output: trust_atom_types::QueryOutput = conductor
+    .call(
+      &cell1.zome("trust_atom"),
+      "query_mine",
+      trust_atom_types::QueryMineInput {
+        target: None,
+        content_full: None,
+        content_starts_with: None,
+        value_starts_with: None,
+        strict: None,
+      },
+    )
+    .await;
+  let trust_atoms = query_output.trust_atoms;
+
+  assert_eq!(trust_atoms.len(), 2);
+}
+
+#[tokio::test(flavor = "multi_thread")]
+pub async fn test_export_my_trust_atoms() {
+  let (conductor, agent, cell1): (SweetConductor, AgentPubKey, SweetCell) =
+    setup_1_conductor().await;
+
+  let target_hash: EntryHash = conductor
+    .call(
+      &cell1.zome("trust_atom"),
+      "create_string_target",
+      string_target("Nuka Sushi"),
+    )
+    .await;
+  let extra = BTreeMap::from([("details".to_string(), "Excellent specials".to_string())]);
+
+  let _result: trust_atom_types::TrustAtom = conductor
+    .call(
+      &cell1.zome("trust_atom"),
+      "create_trust_atom",
+      trust_atom_types::TrustAtomInput {
+        target: AnyLinkableHash::from(target_hash.clone()),
+        content: Some("sushi".to_string()),
+        value: Some("0.8".into()),
+        confidence: None,
+        extra: Some(extra.clone()),
+      },
+    )
+    .await;
+
+  let bundle: trust_atom_types::bundle::TrustAtomBundle = conductor
+    .call(&cell1.zome("trust_atom"), "export_my_trust_atoms", ())
+    .await;
+
+  let verified: trust_atom_types::VerifiedTrustAtomBundle = conductor
+    .call(
+      &cell1.zome("trust_atom"),
+      "verify_trust_atom_bundle",
+      bundle.clone(),
+    )
+    .await;
+
+  assert_eq!(verified.agent, agent);
+  assert_eq!(verified.trust_atoms.len(), 1);
+  let trust_atom = &verified.trust_atoms[0].trust_atom;
+  assert_eq!(trust_atom.target_hash, AnyLinkableHash::from(target_hash));
+  assert_eq!(trust_atom.content, Some("sushi".to_string()));
+  assert_eq!(trust_atom.value, Some(".800000000".to_string()));
+  assert_eq!(trust_atom.extra, Some(extra));
+  assert!(verified.trust_atoms[0].timestamp <= verified.exported_at);
+
+  // TAMPERED BUNDLE
+
+  let tampered = trust_atom_types::bundle::TrustAtomBundle {
+    payload: bundle.payload.replace("0.8", "-0.8"),
+    signature: bundle.signature,
+  };
+  let result: Result<trust_atom_types::VerifiedTrustAtomBundle, _> = conductor
+    .call_fallible(
+      &cell1.zome("trust_atom"),
+      "verify_trust_atom_bundle",
+      tampered,
+    )
+    .await;
+  assert!(result.is_err());
+}
+
+#[tokio::test(flavor = "multi_thread")]
+pub async fn test_trust_atom_credential() {
+  let (conductor, _agent, cell1): (SweetConductor, AgentPubKey, SweetCell) =
+    setup_1_conductor().await;
+
+  let target_hash: EntryHash = conductor
+    .call(
+      &cell1.zome("trust_atom"),
+      "create_string_target",
+      string_target("Nuka Sushi"),
+    )
+    .await;
+
+  let trust_atom: trust_atom_types::TrustAtom = conductor
+    .call(
+      &cell1.zome("trust_atom"),
+      "create_trust_atom",
+      trust_atom_types::TrustAtomInput {
+        target: AnyLinkableHash::from(target_hash.clone()),
+        content: Some("sushi".to_string()),
+        value: Some("0.8".into()),
+        confidence: None,
+        extra: None,
+      },
+    )
+    .await;
+
+  let credential: String = conductor
+    .call(
+      &cell1.zome("trust_atom"),
+      "issue_trust_atom_credential",
+      trust_atom.clone(),
+    )
+    .await;
+
+  let parsed =
+    trust_atom_types::credential::TrustAtomCredential::from_json_str(&credential).unwrap();
+  assert!(parsed.issuer.starts_with("did:key:z6Mk"));
+  assert_eq!(parsed.credential_subject.content, Some("sushi".to_string()));
+  assert_eq!(parsed.proof.unwrap().cryptosuite, "eddsa-jcs-2022");
+
+  let verified: trust_atom_types::TrustAtom = conductor
+    .call(
+      &cell1.zome("trust_atom"),
+      "verify_trust_atom_credential",
+      credential.clone(),
+    )
+    .await;
+
+  assert_eq!(verified.source_hash, trust_atom.source_hash);
+  assert_eq!(verified.target_hash, AnyLinkableHash::from(target_hash));
+  assert_eq!(verified.content, Some("sushi".to_string()));
+  assert_eq!(verified.value, Some(".800000000".to_string()));
+
+  // TAMPERED CREDENTIAL
+
+  let result: Result<trust_atom_types::TrustAtom, _> = conductor
+    .call_fallible(
+      &cell1.zome("trust_atom"),
+      "verify_trust_atom_credential",
+      credential.replace("sushi", "ramen"),
+    )
+    .await;
+  assert!(result.is_err());
+}
+
+#[tokio::test(flavor = "multi_thread")]
+pub async fn test_external_target() {
+  let (conductor, agent, cell1): (SweetConductor, AgentPubKey, SweetCell) =
+    setup_1_conductor().await;
+
+  let ipfs_uri = "https://ipfs.io/ipfs/Qme7ss3ARVgxv6rXqVPiikMJ8u2NLgmgszg13pYrDKEoiu";
+
+  let ipfs_hash: ExternalHash = conductor
+    .call(
+      &cell1.zome("trust_atom"),
+      "create_external_target",
+      ipfs_uri,
+    )
+    .await;
+  assert_eq!(
+    ipfs_hash,
+    trust_atom_types::uri::external_hash_for_uri(ipfs_uri)
+  );
+
+  // idempotent
+  let ipfs_hash_again: ExternalHash = conductor
+    .call(
+      &cell1.zome("trust_atom"),
+      "create_external_target",
+      ipfs_uri,
+    )
+    .await;
+  assert_eq!(ipfs_hash_again, ipfs_hash);
+
+  let uri: Option<String> = conductor
+    .call(
+      &cell1.zome("trust_atom"),
+      "get_external_target_uri",
+      ipfs_hash.clone(),
+    )
+    .await;
+  assert_eq!(uri, Some(ipfs_uri.to_string()));
+
+  let trust_atom: trust_atom_types::TrustAtom = conductor
+    .call(
+      &cell1.zome("trust_atom"),
+      "create_trust_atom",
+      trust_atom_types::TrustAtomInput {
+        target: AnyLinkableHash::from(ipfs_hash.clone()),
+        content: Some("ipfs".to_string()),
+        value: None,
+        confidence: None,
+        extra: None,
+      },
+    )
+    .await;
+
+  let trust_atoms: Vec<trust_atom_types::TrustAtom> = conductor
+    .call(
+      &cell1.zome("trust_atom"),
+      "query_external_target",
+      trust_atom_types::QueryExternalTargetInput {
+        uri: ipfs_uri.to_string(),
+        content_full: None,
+        content_starts_with: None,
+        value_starts_with: None,
+      },
+    )
+    .await;
+
+  assert_eq!(trust_atoms.len(), 1);
+  assert_eq!(trust_atoms[0].source_hash, AnyLinkableHash::from(agent));
+  assert_eq!(trust_atoms[0].target_hash, trust_atom.target_hash);
+  assert_eq!(trust_atoms[0].content, Some("ipfs".to_string()));
+
+  let unknown_uri: Option<String> = conductor
+    .call(
+      &cell1.zome("trust_atom"),
+      "get_external_target_uri",
+      trust_atom_types::uri::external_hash_for_uri("https://trustgraph.net"),
+    )
+    .await;
+  assert_eq!(unknown_uri, None);
+}
+
+#[tokio::test(flavor = "multi_thread")]
+pub async fn test_string_targets() {
+  let (conductor, _agent, cell1): (SweetConductor, AgentPubKey, SweetCell) =
+    setup_1_conductor().await;
+
+  let not_yet_created: Option<EntryHash> = conductor
+    .call(
+      &cell1.zome("trust_atom"),
+      "lookup_string_target",
+      string_target("Café Nuka"),
+    )
+    .await;
+  assert_eq!(not_yet_created, None);
+
+  let target_hash: EntryHash = conductor
+    .call(
+      &cell1.zome("trust_atom"),
+      "create_string_target",
+      string_target("Café Nuka"),
+    )
+    .await;
+
+  // trimmed and NFC normalized (combining accent), and idempotent
+  let same_target_hash: EntryHash = conductor
+    .call(
+      &cell1.zome("trust_atom"),
+      "create_string_target",
+      string_target("  Cafe\u{301} Nuka "),
+    )
+    .await;
+  assert_eq!(same_target_hash, target_hash);
+
+  let looked_up: Option<EntryHash> = conductor
+    .call(
+      &cell1.zome("trust_atom"),
+      "lookup_string_target",
+      string_target("Café Nuka"),
+    )
+    .await;
+  assert_eq!(looked_up, Some(target_hash.clone()));
+
+  // namespaced
+  let isbn_hash: EntryHash = conductor
+    .call(
+      &cell1.zome("trust_atom"),
+      "create_string_target",
+      trust_atom_types::StringTargetInput {
+        text: "9780261103573".to_string(),
+        namespace: Some("ISBN:".to_string()),
+      },
+    )
+    .await;
+  let isbn_looked_up: Option<EntryHash> = conductor
+    .call(
+      &cell1.zome("trust_atom"),
+      "lookup_string_target",
+      trust_atom_types::StringTargetInput {
+        text: "9780261103573".to_string(),
+        namespace: Some("isbn".to_string()),
+      },
+    )
+    .await;
+  assert_eq!(isbn_looked_up, Some(isbn_hash.clone()));
+
+  let unnamespaced: Option<EntryHash> = conductor
+    .call(
+      &cell1.zome("trust_atom"),
+      "lookup_string_target",
+      string_target("9780261103573"),
+    )
+    .await;
+  assert_eq!(unnamespaced, None);
+
+  let result: Result<EntryHash, _> = conductor
+    .call_fallible(
+      &cell1.zome("trust_atom"),
+      "create_string_target",
+      string_target("   "),
+    )
+    .await;
+  assert!(result.is_err());
+}
+
+#[tokio::test(flavor = "multi_thread")]
+pub async fn test_create_trust_atom_over_size_limits() {
+  let (conductor, _agent, cell1): (SweetConductor, AgentPubKey, SweetCell) =
+    setup_1_conductor().await;
+
+  let target_hash: EntryHash = conductor
+    .call(
+      &cell1.zome("trust_atom"),
+      "create_string_target",
+      string_target("Nuka Sushi"),
+    )
+    .await;
+  let trust_atom_input = |content: String| trust_atom_types::TrustAtomInput {
+    target: AnyLinkableHash::from(target_hash.clone()),
+    content: Some(content),
+    value: Some("0.8".into()),
+    confidence: None,
+    extra: None,
+  };
+
+  let result: Result<trust_atom_types::TrustAtom, _> = conductor
+    .call_fallible(
+      &cell1.zome("trust_atom"),
+      "create_trust_atom",
+      trust_atom_input("a".repeat(901)),
+    )
+    .await;
+  let error = format!("{:?}", result.unwrap_err());
+  assert!(
+    error.contains("Content is too large: 901 bytes, but the limit is 900 bytes"),
+    "{error}"
+  );
+
+  let results: Vec<trust_atom_types::CreateTrustAtomResult> = conductor
+    .call(
+      &cell1.zome("trust_atom"),
+      "create_trust_atoms",
+      vec![
+        trust_atom_input("a".repeat(900)),
+        trust_atom_input("é".repeat(451)),
+      ],
+    )
+    .await;
+  assert!(results[0].error.is_none());
+  assert!(results[1]
+    .error
+    .clone()
+    .unwrap()
+    .contains("Content is too large: 902 bytes"));
+}
+
+#[tokio::test(flavor = "multi_thread")]
+pub async fn test_create_trust_atom_with_adversarial_content() {
+  let (conductor, agent, cell1): (SweetConductor, AgentPubKey, SweetCell) =
+    setup_1_conductor().await;
+
+  let target_hash: EntryHash = conductor
+    .call(
+      &cell1.zome("trust_atom"),
+      "create_string_target",
+      string_target("Nuka Sushi"),
+    )
+    .await;
+  let trust_atom_input = |content: &str| trust_atom_types::TrustAtomInput {
+    target: AnyLinkableHash::from(target_hash.clone()),
+    content: Some(content.to_string()),
+    value: Some("-0.4".into()),
+    confidence: None,
+    extra: None,
+  };
+
+  // NUL would shift the value, bucket and extra chunks
+  for content in ["sushi\u{0}.999999999", "\u{0}", "sushi\u{0}"] {
+    let result: Result<trust_atom_types::TrustAtom, _> = conductor
+      .call_fallible(
+        &cell1.zome("trust_atom"),
+        "create_trust_atom",
+        trust_atom_input(content),
+      )
+      .await;
+    let error = format!("{:?}", result.unwrap_err());
+    assert!(error.contains("Content must not contain NUL"), "{error}");
+  }
+
+  let result: Result<trust_atom_types::QueryOutput, _> = conductor
+    .call_fallible(
+      &cell1.zome("trust_atom"),
+      "query_mine",
+      trust_atom_types::QueryMineInput {
+        target: None,
+        content_full: None,
+        content_starts_with: Some("sushi\u{0}.9".to_string()),
+        value_starts_with: None,
+        strict: None,
+      },
+    )
+    .await;
+  assert!(result.is_err());
+
+  // Other reserved-looking characters are just content
+  let adversarial_content = "Ŧ↩\u{2}\u{3}sushi";
+  let _result: trust_atom_types::TrustAtom = conductor
+    .call(
+      &cell1.zome("trust_atom"),
+      "create_trust_atom",
+      trust_atom_input(adversarial_content),
+    )
+    .await;
+
+  let query_output: trust_atom_types::QueryOutput = conductor
+    .call(
+      &cell1.zome("trust_atom"),
+      "query_mine",
+      trust_atom_types::QueryMineInput {
+        target: None,
+        content_full: Some(adversarial_content.to_string()),
+        content_starts_with: None,
+        value_starts_with: None,
+        strict: None,
+      },
+    )
+    .await;
+  let trust_atoms = query_output.trust_atoms;
+  assert_eq!(
+    trust_atoms,
+    vec![trust_atom_types::TrustAtom {
+      source_hash: AnyLinkableHash::from(agent),
+      target_hash: AnyLinkableHash::from(target_hash),
+      content: Some(adversarial_content.to_string()),
+      display_content: None,
+      confidence: None,
+      value: Some("-.400000000".to_string()),
+      extra: Some(BTreeMap::new()),
+    }]
+  );
+}
+
+#[tokio::test(flavor = "multi_thread")]
+pub async fn test_migrate_my_trust_atoms() {
+  let (conductor, agent, cell1): (SweetConductor, AgentPubKey, SweetCell) =
+    setup_1_conductor().await;
+
+  let mut targets: Vec<AnyLinkableHash> = vec![];
+  for name in ["Nuka Sushi", "Sushiya", "Ramen Shop"] {
+    let target_hash: EntryHash = conductor
+      .call(
+        &cell1.zome("trust_atom"),
+        "create_string_target",
+        string_target(name),
+      )
+      .await;
+    targets.push(AnyLinkableHash::from(target_hash));
+  }
+
+  let _result: trust_atom_types::TrustAtom = conductor
+    .call(
+      &cell1.zome("trust_atom"),
+      "create_trust_atom",
+      trust_atom_types::TrustAtomInput {
+        target: targets[0].clone(),
+        content: Some("sushi".to_string()),
+        value: Some("0.8".into()),
+        confidence: None,
+        extra: None,
+      },
+    )
+    .await;
+  for (target, content) in [(&targets[1], "sushi"), (&targets[2], "ramen")] {
+    let _result: () = conductor
+      .call(
+        &cell1.zome("trust_atom"),
+        "test_helper_create_v1_trust_atom",
+        trust_atom_types::TrustAtomInput {
+          target: target.clone(),
+          content: Some(content.to_string()),
+          value: Some(".500000000".into()),
+          confidence: None,
+          extra: None,
+        },
+      )
+      .await;
+  }
+
+  let input = |batch_size, dry_run| trust_atom_types::MigrateTrustAtomsInput {
+    batch_size,
+    dry_run,
+  };
+  let report = |migrated, remaining, current, dry_run| trust_atom_types::MigrationReport {
+    dry_run,
+    migrated,
+    remaining,
+    current,
+    undecodable: 0,
+    failed: vec![],
+  };
+
+  for (batch_size, dry_run, expected) in [
+    (10, true, report(2, 0, 1, true)),
+    (1, false, report(1, 1, 1, false)),
+    (10, false, report(1, 0, 2, false)),
+    (10, false, report(0, 0, 3, false)),
+  ] {
+    let actual: trust_atom_types::MigrationReport = conductor
+      .call(
+        &cell1.zome("trust_atom"),
+        "migrate_my_trust_atoms",
+        input(batch_size, dry_run),
+      )
+      .await;
+    assert_eq!(actual, expected);
+  }
+
+  let forward_links: Vec<Link> = conductor
+    .call(
+      &cell1.zome("trust_atom"),
+      "test_helper_list_links_for_base",
+      AnyLinkableHash::from(agent.clone()),
+    )
+    .await;
+  assert_eq!(forward_links.len(), 3);
+  for link in forward_links {
+    assert!(link.tag.into_inner().starts_with("Ŧ→\u{2}".as_bytes()));
+  }
+
+  let reverse_links: Vec<Link> = conductor
+    .call(
+      &cell1.zome("trust_atom"),
+      "test_helper_list_links_for_base",
+      targets[2].clone(),
+    )
+    .await;
+  assert_eq!(reverse_links.len(), 1);
+  assert!(reverse_links[0]
+    .tag
+    .clone()
+    .into_inner()
+    .starts_with("Ŧ↩\u{2}ramen\u{0}.500000000".as_bytes()));
+
+  let query_output: trust_atom_types::QueryOutput = conductor
+    .call(
+      &cell1.zome("trust_atom"),
+      "query_mine",
+      trust_atom_types::QueryMineInput {
+        target: None,
+        content_full: Some("sushi".to_string()),
+        content_starts_with: None,
+        value_starts_with: None,
+        strict: None,
+      },
+    )
+    .await;
+  let trust_atoms = query_output.trust_atoms;
+  assert_eq!(trust_atoms.len(), 2);
+}
+
+#[tokio::test(flavor = "multi_thread")]
+pub async fn test_migrate_my_trust_atoms__skips_atoms_which_cannot_be_migrated() {
+  let (conductor, agent, cell1): (SweetConductor, AgentPubKey, SweetCell) =
+    setup_1_conductor().await;
+
+  let mut targets: Vec<AnyLinkableHash> = vec![];
+  for name in ["Nuka Sushi", "Sushiya"] {
+    let target_hash: EntryHash = conductor
+      .call(
+        &cell1.zome("trust_atom"),
+        "create_string_target",
+        string_target(name),
+      )
+      .await;
+    targets.push(AnyLinkableHash::from(target_hash));
+  }
+
+  // exactly 999 bytes as version 1, so one byte over the limit with a version byte
+  let _result: () = conductor
+    .call(
+      &cell1.zome("trust_atom"),
+      "test_helper_create_v1_trust_atom",
+      trust_atom_types::TrustAtomInput {
+        target: targets[0].clone(),
+        content: Some("a".repeat(900)),
+        value: Some("0.0000000000000000000123456789".into()),
+        confidence: None,
+        extra: Some(BTreeMap::from([(
+          "details".to_string(),
+          "Excellent specials".to_string(),
+        )])),
+      },
+    )
+    .await;
+  let _result: () = conductor
+    .call(
+      &cell1.zome("trust_atom"),
+      "test_helper_create_v1_trust_atom",
+      trust_atom_types::TrustAtomInput {
+        target: targets[1].clone(),
+        content: Some("sushi".to_string()),
+        value: Some(".500000000".into()),
+        confidence: None,
+        extra: None,
+      },
+    )
+    .await;
+
+  let forward_links: Vec<Link> = conductor
+    .call(
+      &cell1.zome("trust_atom"),
+      "test_helper_list_links_for_base",
+      AnyLinkableHash::from(agent.clone()),
+    )
+    .await;
+  let unmigratable = forward_links
+    .iter()
+    .find(|link| link.target == targets[0])
+    .unwrap();
+  assert_eq!(unmigratable.tag.0.len(), 999);
+
+  // the atom which can't be migrated doesn't stop the others, or the next call
+  for (migrated, current) in [(1, 0), (0, 1)] {
+    let report: trust_atom_types::MigrationReport = conductor
+      .call(
+        &cell1.zome("trust_atom"),
+        "migrate_my_trust_atoms",
+        trust_atom_types::MigrateTrustAtomsInput {
+          batch_size: 10,
+          dry_run: false,
+        },
+      )
+      .await;
+    assert_eq!(
+      report,
+      trust_atom_types::MigrationReport {
+        dry_run: false,
+        migrated,
+        remaining: 0,
+        current,
+        undecodable: 0,
+        failed: vec![unmigratable.create_link_hash.clone()],
+      }
+    );
+  }
+
+  let forward_links: Vec<Link> = conductor
+    .call(
+      &cell1.zome("trust_atom"),
+      "test_helper_list_links_for_base",
+      AnyLinkableHash::from(agent),
+    )
+    .await;
+  assert_eq!(forward_links.len(), 2);
+  assert!(forward_links
+    .iter()
+    .any(|link| link.create_link_hash == unmigratable.create_link_hash));
+}
+
+#[tokio::test(flavor = "multi_thread")]
+pub async fn test_create_trust_atom_with_typed_value() {
+  let (conductor, _agent, cell1): (SweetConductor, AgentPubKey, SweetCell) =
+    setup_1_conductor().await;
+
+  let target_hash: EntryHash = conductor
+    .call(
+      &cell1.zome("trust_atom"),
+      "create_string_target",
+      string_target("Nuka Sushi"),
+    )
+    .await;
+  let target = AnyLinkableHash::from(target_hash);
+
+  let trust_atom: trust_atom_types::TrustAtom = conductor
+    .call(
+      &cell1.zome("trust_atom"),
+      "create_trust_atom",
+      trust_atom_types::TrustAtomInput {
+        target: target.clone(),
+        content: Some("sushi".to_string()),
+        value: Some(TrustAtomValue::Stars { n: 4, max: 5 }),
+        confidence: None,
+        extra: None,
+      },
+    )
+    .await;
+  assert_eq!(trust_atom.value, Some(".500000000".to_string()));
+  assert_eq!(
+    trust_atom.extra,
+    Some(BTreeMap::from([(
+      ORIGINAL_VALUE_EXTRA_KEY.to_string(),
+      r#"{"n":4,"max":5}"#.to_string()
+    )]))
+  );
+
+  let query_output: trust_atom_types::QueryOutput = conductor
+    .call(
+      &cell1.zome("trust_atom"),
+      "query_mine",
+      trust_atom_types::QueryMineInput {
+        target: Some(target.clone()),
+        content_full: None,
+        content_starts_with: None,
+        value_starts_with: None,
+        strict: None,
+      },
+    )
+    .await;
+  let values: Vec<Option<String>> = query_output
+    .trust_atoms
+    .into_iter()
+    .map(|trust_atom| trust_atom.value)
+    .collect();
+  assert_eq!(values, vec![Some(".500000000".to_string())]);
+
+  for value in [
+    TrustAtomValue::Stars { n: 6, max: 5 },
+    TrustAtomValue::Percent { percent: 101 },
+  ] {
+    let result: Result<trust_atom_types::TrustAtom, _> = conductor
+      .call_fallible(
+        &cell1.zome("trust_atom"),
+        "create_trust_atom",
+        trust_atom_types::TrustAtomInput {
+          target: target.clone(),
+          content: Some("sushi".to_string()),
+          value: Some(value),
+          confidence: None,
+          extra: None,
+        },
+      )
+      .await;
+    assert!(result.is_err());
+  }
+}
+
+#[tokio::test(flavor = "multi_thread")]
+pub async fn test_summarize_target_weighted_by_confidence() {
+  let (conductors, _agents, apps) = setup_conductors(2).await;
+  let cells = apps.cells_flattened();
+  let (conductor_sure, conductor_unsure) = (&conductors[0], &conductors[1]);
+  let (cell_sure, cell_unsure) = (&cells[0], &cells[1]);
+
+  let target_hash: EntryHash = conductor_sure
+    .call(
+      &cell_sure.zome("trust_atom"),
+      "create_string_target",
+      string_target("Nuka Sushi"),
+    )
+    .await;
+  let target = AnyLinkableHash::from(target_hash);
+
+  let sure: trust_atom_types::TrustAtom = conductor_sure
+    .call(
+      &cell_sure.zome("trust_atom"),
+      "create_trust_atom",
+      trust_atom_types::TrustAtomInput {
+        target: target.clone(),
+        content: Some("sushi".into()),
+        value: Some("0.8".into()),
+        confidence: None,
+        extra: None,
+      },
+    )
+    .await;
+  assert_eq!(sure.confidence, None);
+
+  let unsure: trust_atom_types::TrustAtom = conductor_unsure
+    .call(
+      &cell_unsure.zome("trust_atom"),
+      "create_trust_atom",
+      trust_atom_types::TrustAtomInput {
+        target: target.clone(),
+        content: Some("sushi".into()),
+        value: Some("-0.8".into()),
+        confidence: Some("0.25".into()),
+        extra: None,
+      },
+    )
+    .await;
+  assert_eq!(unsure.confidence, Some(".250000000".into()));
+
+  consistency_10s([cell_sure, cell_unsure]).await;
+
+  // CONFIDENCE IS READ BACK FROM THE LINK TAG
+
+  let query_output: trust_atom_types::QueryOutput = conductor_sure
+    .call(
+      &cell_sure.zome("trust_atom"),
+      "query",
+      trust_atom_types::QueryInput {
+        source: None,
+        target: Some(target.clone()),
+        content_full: None,
+        content_starts_with: None,
+        value_starts_with: None,
+        strict: None,
+      },
+    )
+    .await;
+  let mut confidences: Vec<Option<String>> = query_output
+    .trust_atoms
+    .into_iter()
+    .map(|trust_atom| trust_atom.confidence)
+    .collect();
+  confidences.sort();
+  assert_eq!(confidences, vec![None, Some(".250000000".into())]);
+
+  // (.8 * 1 + -.8 * .25) / (1 + .25)
+
+  let summary: trust_atom_types::TargetSummary = conductor_sure
+    .call(
+      &cell_sure.zome("trust_atom"),
+      "summarize_target",
+      trust_atom_types::SummarizeTargetInput {
+        target: target.clone(),
+        content_full: Some("sushi".into()),
+        content_starts_with: None,
+        weighting: None,
+      },
+    )
+    .await;
+  assert_eq!(summary.value, Some(".480000000".into()));
+  assert_eq!(summary.ratings_counted, 2);
+
+  let result: Result<trust_atom_types::TrustAtom, _> = conductor_unsure
+    .call_fallible(
+      &cell_unsure.zome("trust_atom"),
+      "create_trust_atom",
+      trust_atom_types::TrustAtomInput {
+        target,
+        content: Some("sushi".into()),
+        value: Some("0.5".into()),
+        confidence: Some("1.5".into()),
+        extra: None,
+      },
+    )
+    .await;
+  assert!(result.is_err());
+}
+
+#[tokio::test(flavor = "multi_thread")]
+pub async fn test_cached_rollup_staleness__weighted_by_caller() {
+  let (conductors, agents, apps) = setup_conductors(2).await;
+  let cells = apps.cells_flattened();
+  let (conductor_me, conductor_seed) = (&conductors[0], &conductors[1]);
+  let (cell_me, cell_seed) = (&cells[0], &cells[1]);
+
+  let target_hash: EntryHash = conductor_seed
+    .call(
+      &cell_seed.zome("trust_atom"),
+      "create_string_target",
+      string_target("Nuka Sushi"),
+    )
+    .await;
+  let _result: trust_atom_types::TrustAtom = conductor_seed
+    .call(
+      &cell_seed.zome("trust_atom"),
+      "create_trust_atom",
+      trust_atom_types::TrustAtomInput {
+        target: AnyLinkableHash::from(target_hash),
+        content: Some("sushi".into()),
+        value: Some("0.8".into()),
+        confidence: None,
+        extra: None,
+      },
+    )
+    .await;
+
+  consistency_10s([cell_me, cell_seed]).await;
+
+  let rollup_input = |weighting| trust_atom_types::RollupInput {
+    seed: Some(AnyLinkableHash::from(agents[1].clone())),
+    content_full: None,
+    content_starts_with: Some("sushi".into()),
+    weighting,
+  };
+  let weightings = [None, Some(trust_atom_types::RaterWeighting::IncomingTrust)];
+
+  for weighting in weightings {
+    let _cached: trust_atom_types::CachedRollupOutput = conductor_me
+      .call(
+        &cell_me.zome("trust_atom"),
+        "cache_rollup",
+        trust_atom_types::CacheRollupInput {
+          rollup: rollup_input(weighting),
+          private: true,
+        },
+      )
+      .await;
+  }
+
+  // CHANGE MY OWN FIRST HOP, NOT THE SEED'S
+
+  let _result: trust_atom_types::TrustAtom = conductor_me
+    .call(
+      &cell_me.zome("trust_atom"),
+      "create_trust_atom",
+      trust_atom_types::TrustAtomInput {
+        target: AnyLinkableHash::from(agents[1].clone()),
+        content: Some("foodie".into()),
+        value: Some("0.9".into()),
+        confidence: None,
+        extra: None,
+      },
+    )
+    .await;
+
+  let mut stale = vec![];
+  for weighting in weightings {
+    let cached: trust_atom_types::CachedRollupOutput = conductor_me
+      .call(
+        &cell_me.zome("trust_atom"),
+        "get_cached_rollup",
+        trust_atom_types::GetCachedRollupInput {
+          rollup: rollup_input(weighting),
+          refresh_if_stale: false,
+          private: true,
+        },
+      )
+      .await;
+    stale.push(cached.stale);
+  }
+  assert_eq!(stale, vec![false, true]);
+}
 
-//   //// WIP
+#[tokio::test(flavor = "multi_thread")]
+pub async fn test_external_target__spoofed_uri_rejected() {
+  let (conductor, _agent, cell1): (SweetConductor, AgentPubKey, SweetCell) =
+    setup_1_conductor().await;
+
+  let uri = "https://trustgraph.net";
+  let external_hash = trust_atom_types::uri::external_hash_for_uri(uri);
+
+  let result: Result<(), _> = conductor
+    .call_fallible(
+      &cell1.zome("trust_atom"),
+      "test_helper_create_external_target_link",
+      (
+        external_hash.clone(),
+        "https://example.com/spoof".to_string(),
+      ),
+    )
+    .await;
+  assert!(result.is_err());
 
-//   let ipfs_address = ExternalHash::from("https://ipfs.io/ipfs/Qme7ss3ARVgxv6rXqVPiikMJ8u2NLgmgszg13pYrDKEoiu".to_string());
-//   let hc_linked_ipfs_address = AnyLinkableHash::from(ipfs_address.clone());
+  let created_hash: ExternalHash = conductor
+    .call(&cell1.zome("trust_atom"), "create_external_target", uri)
+    .await;
+  assert_eq!(created_hash, external_hash);
 
-//   let mock_input = TrustAtomInput {
-//     target: AnyLinkableHash::from(ipfs_address),
-//     content: Some("ipfs".to_string()),
-//     value: None,
-//     extra: None,
-//   };
+  let resolved_uri: Option<String> = conductor
+    .call(
+      &cell1.zome("trust_atom"),
+      "get_external_target_uri",
+      external_hash,
+    )
+    .await;
+  assert_eq!(resolved_uri, Some(uri.to_string()));
+}
 
-//   let mock_trust_atom: TrustAtom = conductor
-//     .call(
-//       &cell1.zome("trust_atom"),
-//       "create_trust_atom",
-//       mock_input.clone(),
-//     )
-//     .await;
+#[tokio::test(flavor = "multi_thread")]
+pub async fn test_query_empty_content_prefix_returns_each_atom_once() {
+  let (conductor, _agent, cell1): (SweetConductor, AgentPubKey, SweetCell) =
+    setup_1_conductor().await;
 
-//   let external_link = get_links(AnyLinkableHash::from(ipfs_address), None);
+  let target_hash: EntryHash = conductor
+    .call(
+      &cell1.zome("trust_atom"),
+      "create_string_target",
+      string_target("Nuka Sushi"),
+    )
+    .await;
+  let target = AnyLinkableHash::from(target_hash);
+  let input = |content: &str| trust_atom_types::TrustAtomInput {
+    target: target.clone(),
+    content: Some(content.to_string()),
+    value: Some(".500000000".into()),
+    confidence: None,
+    extra: None,
+  };
 
-//   let fetched_atom = convert_link_to_trust_atom(external_link, LinkDirection::Reverse, AnyLinkableHash::from(agent))?;
+  let _result: () = conductor
+    .call(
+      &cell1.zome("trust_atom"),
+      "test_helper_create_v1_trust_atom",
+      input("ramen"),
+    )
+    .await;
+  let _result: trust_atom_types::TrustAtom = conductor
+    .call(
+      &cell1.zome("trust_atom"),
+      "create_trust_atom",
+      input("sushi"),
+    )
+    .await;
 
-//   println!("external_link: {:?}", external_link);
-//   assert_eq!(mock_trust_atom, fetched_atom);
-//   assert_eq!(fetched_atom.target, ipfs_address);
-// }
+  // the version 1 prefix, with no content, is also a prefix of the version 2 tag
+  let query_output: trust_atom_types::QueryOutput = conductor
+    .call(
+      &cell1.zome("trust_atom"),
+      "query",
+      trust_atom_types::QueryInput {
+        source: None,
+        target: Some(target.clone()),
+        content_full: None,
+        content_starts_with: Some(String::new()),
+        value_starts_with: None,
+        strict: None,
+      },
+    )
+    .await;
+  let mut contents: Vec<Option<String>> = query_output
+    .trust_atoms
+    .into_iter()
+    .map(|trust_atom| trust_atom.content)
+    .collect();
+  contents.sort();
+  assert_eq!(
+    contents,
+    vec![Some("ramen".to_string()), Some("sushi".to_string())]
+  );
+}
 
 // TESTING UTILITY FUNCTIONS
 
+fn string_target(text: &str) -> trust_atom_types::StringTargetInput {
+  trust_atom_types::StringTargetInput {
+    text: text.to_string(),
+    namespace: None,
+  }
+}
+
 async fn setup_1_conductor() -> (SweetConductor, AgentPubKey, SweetCell) {
   let dna = SweetDnaFile::from_bundle(std::path::Path::new(DNA_FILEPATH))
     .await
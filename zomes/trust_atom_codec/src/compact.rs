@@ -0,0 +1,309 @@
+//! Body of `TagVersion::Compact` tags, after the version byte:
+//!
+//! ```text
+//! content NUL flags [value: 4 bytes] [bucket: 4 bytes] [extra-hash: 39 bytes]
+//!   [confidence: 4 bytes] [display-content]
+//! ```
+//!
+//! `flags` says which of the fields follow.  `display-content` is text running to the end of the
+//! tag.  `value` is in billionths, as a big-endian `i32` with its sign bit flipped so that values
+//! sort bytewise, and `confidence` is stored the same way; `bucket` is a big-endian `u32`;
+//! `extra-hash` is the raw `EntryHash`.  Content stays first and text, so prefix searches on it work
+//! just as in the text versions.
+
+use crate::{DecodeError, EncodeError};
+
+const VALUE_FLAG: u8 = 0b001;
+const BUCKET_FLAG: u8 = 0b010;
+const EXTRA_HASH_FLAG: u8 = 0b100;
+const DISPLAY_CONTENT_FLAG: u8 = 0b1000;
+const CONFIDENCE_FLAG: u8 = 0b1_0000;
+
+const VALUE_LEN: usize = 4;
+const BUCKET_LEN: usize = 4;
+const EXTRA_HASH_LEN: usize = 39; // 3 byte type prefix, 32 byte hash, 4 byte DHT location
+const NANOS: i32 = 1_000_000_000;
+const MAX_BUCKET: u32 = 999_999_999;
+
+const BASE64_URL: &[u8; 64] = b"ABCDEFGHIJKLMNOPQRSTUVWXYZabcdefghijklmnopqrstuvwxyz0123456789-_";
+
+/// Fixed-width fields of a compact tag, as the strings the text versions hold
+pub struct Fields {
+  pub value: Option<String>,
+  pub bucket: Option<String>,
+  pub extra_hash: Option<String>,
+  pub confidence: Option<String>,
+  pub display_content: Option<String>,
+}
+
+pub fn encode_fields(
+  value: Option<&str>,
+  bucket: Option<&str>,
+  extra_hash: Option<&str>,
+  confidence: Option<&str>,
+  display_content: Option<&str>,
+) -> Result<Vec<u8>, EncodeError> {
+  let mut flags = 0;
+  let mut bytes = vec![];
+
+  if let Some(value) = value {
+    flags |= VALUE_FLAG;
+    bytes.extend_from_slice(&encode_value(value)?);
+  }
+  if let Some(bucket) = bucket {
+    flags |= BUCKET_FLAG;
+    let bucket_number = (bucket.len() <= 9 && bucket.bytes().all(|b| b.is_ascii_digit()))
+      .then(|| bucket.parse::<u32>().ok())
+      .flatten()
+      .ok_or(EncodeError::InvalidBucket(bucket.to_string()))?;
+    bytes.extend_from_slice(&bucket_number.to_be_bytes());
+  }
+  if let Some(extra_hash) = extra_hash {
+    flags |= EXTRA_HASH_FLAG;
+    let raw = extra_hash
+      .strip_prefix('u')
+      .and_then(base64_url_decode)
+      .filter(|raw| raw.len() == EXTRA_HASH_LEN)
+      .ok_or(EncodeError::InvalidExtraHash(extra_hash.to_string()))?;
+    bytes.extend_from_slice(&raw);
+  }
+  if let Some(confidence) = confidence {
+    flags |= CONFIDENCE_FLAG;
+    bytes.extend_from_slice(&encode_value(confidence)?);
+  }
+  if let Some(display_content) = display_content {
+    flags |= DISPLAY_CONTENT_FLAG;
+    bytes.extend_from_slice(display_content.as_bytes());
+  }
+
+  Ok([vec![flags], bytes].concat())
+}
+
+pub fn decode_fields(bytes: &[u8]) -> Result<Fields, DecodeError> {
+  let (&flags, mut rest) = bytes.split_first().ok_or(DecodeError::CompactFields)?;
+  if flags & !(VALUE_FLAG | BUCKET_FLAG | EXTRA_HASH_FLAG | CONFIDENCE_FLAG | DISPLAY_CONTENT_FLAG)
+    != 0
+  {
+    return Err(DecodeError::CompactFields);
+  }
+  let mut take = |flag: u8, len: usize| -> Result<Option<&[u8]>, DecodeError> {
+    if flags & flag == 0 {
+      return Ok(None);
+    }
+    if rest.len() < len {
+      return Err(DecodeError::CompactFields);
+    }
+    let (field, remaining) = rest.split_at(len);
+    rest = remaining;
+    Ok(Some(field))
+  };
+
+  let value = take(VALUE_FLAG, VALUE_LEN)?.map(decode_value).transpose()?;
+  let bucket = take(BUCKET_FLAG, BUCKET_LEN)?
+    .map(decode_bucket)
+    .transpose()?;
+  let extra_hash =
+    take(EXTRA_HASH_FLAG, EXTRA_HASH_LEN)?.map(|field| format!("u{}", base64_url_encode(field)));
+  let confidence = take(CONFIDENCE_FLAG, VALUE_LEN)?
+    .map(decode_value)
+    .transpose()?;
+
+  let display_content = if flags & DISPLAY_CONTENT_FLAG == 0 {
+    if !rest.is_empty() {
+      return Err(DecodeError::CompactFields);
+    }
+    None
+  } else {
+    let display_content = std::str::from_utf8(rest).map_err(|_| DecodeError::InvalidUtf8)?;
+    Some(display_content.to_string())
+  };
+  Ok(Fields {
+    value,
+    bucket,
+    extra_hash,
+    confidence,
+    display_content,
+  })
+}
+
+fn encode_value(value: &str) -> Result<[u8; VALUE_LEN], EncodeError> {
+  let nanos = value_nanos(value).ok_or(EncodeError::InvalidValue(value.to_string()))?;
+  let mut value_bytes = nanos.to_be_bytes();
+  value_bytes[0] ^= 0x80;
+  Ok(value_bytes)
+}
+
+fn decode_value(field: &[u8]) -> Result<String, DecodeError> {
+  let mut value_bytes = [0; VALUE_LEN];
+  value_bytes.copy_from_slice(field);
+  value_bytes[0] ^= 0x80;
+  let nanos = i32::from_be_bytes(value_bytes);
+  if nanos.unsigned_abs() >= NANOS.unsigned_abs() {
+    return Err(DecodeError::CompactFields);
+  }
+  Ok(format_value(nanos))
+}
+
+fn decode_bucket(field: &[u8]) -> Result<String, DecodeError> {
+  let mut bucket_bytes = [0; BUCKET_LEN];
+  bucket_bytes.copy_from_slice(field);
+  let bucket = u32::from_be_bytes(bucket_bytes);
+  if bucket > MAX_BUCKET {
+    return Err(DecodeError::CompactFields);
+  }
+  Ok(format!("{bucket:09}"))
+}
+
+/// Value in billionths, rounded half away from zero, as `normalize_value` does
+fn value_nanos(value: &str) -> Option<i32> {
+  let (negative, unsigned) = match value.strip_prefix('-') {
+    Some(unsigned) => (true, unsigned),
+    None => (false, value),
+  };
+  let (integer, fraction) = unsigned.split_once('.').unwrap_or((unsigned, ""));
+  if (integer.is_empty() && fraction.is_empty())
+    || !integer.bytes().all(|b| b == b'0')
+    || !fraction.bytes().all(|b| b.is_ascii_digit())
+  {
+    return None;
+  }
+
+  let digits = fraction.as_bytes();
+  let mut nanos: i32 = (0..9).fold(0, |nanos, i| {
+    nanos * 10 + i32::from(digits.get(i).map_or(0, |digit| digit - b'0'))
+  });
+  if digits.get(9).map_or(false, |&digit| digit >= b'5') {
+    nanos += 1;
+  }
+  let nanos = nanos.min(NANOS - 1); // `1` is stored as `.999999999`
+  Some(if negative { -nanos } else { nanos })
+}
+
+/// Canonical form, as `normalize_value` writes it (eg `-.800000000`, `0`)
+fn format_value(nanos: i32) -> String {
+  if nanos == 0 {
+    return "0".to_string();
+  }
+  let sign = if nanos < 0 { "-" } else { "" };
+  format!("{sign}.{:09}", nanos.unsigned_abs())
+}
+
+fn base64_url_encode(bytes: &[u8]) -> String {
+  let mut encoded = String::new();
+  for group in bytes.chunks(3) {
+    let bits = group.iter().enumerate().fold(0_u32, |bits, (i, &byte)| {
+      bits | u32::from(byte) << (16 - 8 * i)
+    });
+    for i in 0..=group.len() {
+      encoded.push(char::from(
+        BASE64_URL[(bits >> (18 - 6 * i) & 0x3f) as usize],
+      ));
+    }
+  }
+  encoded
+}
+
+fn base64_url_decode(encoded: &str) -> Option<Vec<u8>> {
+  if encoded.len() % 4 == 1 {
+    return None;
+  }
+  let mut bytes = vec![];
+  for group in encoded.as_bytes().chunks(4) {
+    let mut bits = 0_u32;
+    for (i, &c) in group.iter().enumerate() {
+      let sextet = BASE64_URL.iter().position(|&b| b == c)?;
+      bits |= u32::try_from(sextet).ok()? << (18 - 6 * i);
+    }
+    for i in 0..group.len() - 1 {
+      bytes.push(bits.to_be_bytes()[i + 1]);
+    }
+  }
+  Some(bytes)
+}
+
+#[cfg(test)]
+#[allow(clippy::unwrap_used)]
+#[allow(non_snake_case)]
+mod tests {
+
+  use super::*;
+
+  #[test]
+  fn test_value_nanos() {
+    let input_and_expected = [
+      [".800000000", ".800000000"],
+      ["-.800000000", "-.800000000"],
+      ["0.5", ".500000000"],
+      [".999999999", ".999999999"],
+      ["-.999999999", "-.999999999"],
+      ["0", "0"],
+      ["-0", "0"],
+      [".0000000001", "0"],
+      [".0123456789", ".012345679"],
+      ["-.0123456785", "-.012345679"],
+      ["-.0123456784", "-.012345678"],
+    ];
+
+    for [input, expected] in input_and_expected {
+      assert_eq!(format_value(value_nanos(input).unwrap()), expected);
+    }
+
+    for invalid in ["", "-", "1.5", "foo", ".8e1", " .8"] {
+      assert_eq!(value_nanos(invalid), None, "{invalid}");
+    }
+  }
+
+  #[test]
+  fn test_decode_fields() {
+    let fields =
+      decode_fields(&encode_fields(Some("0"), Some("000000042"), None, None, None).unwrap())
+        .unwrap();
+    assert_eq!(fields.value, Some("0".to_string()));
+    assert_eq!(fields.bucket, Some("000000042".to_string()));
+
+    let oversized_bucket = [&[BUCKET_FLAG][..], &1_000_000_000_u32.to_be_bytes()].concat();
+    assert!(matches!(
+      decode_fields(&oversized_bucket),
+      Err(DecodeError::CompactFields)
+    ));
+
+    let mut oversized_value = NANOS.to_be_bytes();
+    oversized_value[0] ^= 0x80;
+    assert!(matches!(
+      decode_fields(&[&[VALUE_FLAG][..], &oversized_value].concat()),
+      Err(DecodeError::CompactFields)
+    ));
+  }
+
+  #[test]
+  fn test_encode_fields__values_sort_bytewise() {
+    let values = [
+      "-.999999999",
+      "-.5",
+      "-.000000001",
+      "0",
+      ".000000001",
+      ".5",
+      ".999999999",
+    ];
+    let encoded: Vec<Vec<u8>> = values
+      .iter()
+      .map(|value| encode_fields(Some(value), None, None, None, None).unwrap())
+      .collect();
+
+    let mut sorted = encoded.clone();
+    sorted.sort();
+    assert_eq!(encoded, sorted);
+  }
+
+  #[test]
+  fn test_base64_url() {
+    let hash = "uhCEkto76kYgGIZMzU6AbEzCx1HMRNzurwPaOdF2utJaP-33mdcdN";
+    let raw = base64_url_decode(&hash[1..]).unwrap();
+    assert_eq!(raw.len(), EXTRA_HASH_LEN);
+    assert_eq!(format!("u{}", base64_url_encode(&raw)), hash);
+
+    assert_eq!(base64_url_decode("a"), None);
+    assert_eq!(base64_url_decode("ab=c"), None);
+  }
+}
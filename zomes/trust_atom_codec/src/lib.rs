@@ -0,0 +1,902 @@
+#![warn(warnings)]
+#![deny(clippy::all)]
+#![deny(clippy::pedantic)]
+#![deny(clippy::nursery)]
+#![deny(clippy::unwrap_used)]
+#![deny(clippy::unwrap_in_result)]
+#![allow(clippy::missing_errors_doc)] // TODO fix and remove this
+#![allow(clippy::missing_const_for_fn)]
+#![allow(clippy::or_fun_call)]
+#![allow(clippy::option_if_let_else)]
+// #![warn(clippy::cargo)]
+
+//! Link tag format of trust atoms, for the zomes and for anything else reading them off the wire.
+//!
+//! A tag is a header, `Ŧ` followed by `→` (forward link, source to target) or `↩` (reverse link,
+//! target to source), then a format version byte, then these chunks, separated by NUL:
+//!
+//! ```text
+//! content NUL value NUL bucket NUL extra-hash [NUL display-content [NUL confidence]]
+//! ```
+//!
+//! where `bucket` is 9 random digits and `extra-hash` is the base64 `EntryHash` of the `Extra`
+//! entry, if any.  Missing chunks are empty.  `display-content` is the content as entered, only
+//! written when content normalization changed it, or as an empty chunk before a `confidence`.
+//!
+//! Version 1 tags, written before the version byte was introduced, have the chunks directly after
+//! the arrow.  Version bytes are ASCII control characters (below `0x20`), which content rarely
+//! starts with, but version 1 content was never checked for them.  So a tag starting with a control
+//! character is decoded as that version, and if that fails, as version 1.  A version 1 tag whose
+//! content starts with `0x02` or `0x03` and which also decodes as that version is misread.
+//!
+//! `TagVersion::Compact` tags store everything but the content as fixed-width binary, leaving more
+//! of the 999 byte link tag limit for content; see the `compact` module.
+
+use std::fmt;
+
+mod compact;
+
+pub const UNICODE_NUL_STR: &str = "\u{0}"; // Unicode NUL character
+pub const LINK_TAG_HEADER: [u8; 2] = [197, 166]; // Unicode "Ŧ" // hex bytes: [0xC5][0xA6]
+pub const LINK_TAG_ARROW_FORWARD: [u8; 3] = [226, 134, 146]; // Unicode "→" // hex bytes: [0xE2][0x86][0x92]
+pub const LINK_TAG_ARROW_REVERSE: [u8; 3] = [226, 134, 169]; // Unicode "↩" // hex bytes: [0xE2][0x86][0xA9]
+
+const CHUNK_COUNT: usize = 6;
+
+/// Holochain's link tag limit
+pub const MAX_TAG_BYTES: usize = 999;
+pub const MAX_CONTENT_BYTES: usize = 900;
+/// Longest canonical value or confidence: values keep 9 significant figures, so small ones carry
+/// leading zeros, up to 28 decimal places (eg `-.0000000000000000000123456789`)
+pub const MAX_VALUE_CHARS: usize = 30;
+
+/// Format of the tag after the header
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord)]
+pub enum TagVersion {
+  /// No version byte
+  V1,
+  /// Version byte `0x02`, then the same chunks as `V1`
+  V2,
+  /// Version byte `0x03`, then content and binary fields; value is kept to 9 decimal places
+  Compact,
+}
+
+impl TagVersion {
+  /// Text version written for new trust atoms
+  pub const LATEST: Self = Self::V2;
+  /// Every version `decode` accepts; a prefix search must be made for each to find all atoms
+  pub const ALL: [Self; 3] = [Self::V1, Self::V2, Self::Compact];
+
+  /// Version byte following the arrow, if any
+  #[must_use]
+  pub fn byte(self) -> Option<u8> {
+    match self {
+      Self::V1 => None,
+      Self::V2 => Some(2),
+      Self::Compact => Some(3),
+    }
+  }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Direction {
+  /// Link from the source agent to the target
+  Forward,
+  /// Link from the target back to the source agent
+  Reverse,
+}
+
+/// Everything a trust atom link tag holds
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct TrustAtomTag {
+  pub direction: Direction,
+  pub version: TagVersion,
+  pub content: Option<String>,
+  pub value: Option<String>,
+  pub bucket: Option<String>,
+  /// `Extra` entry hash, base64 encoded (`uhCEk…`)
+  pub extra_hash: Option<String>,
+  /// Content as entered, if normalizing it for `content` changed it
+  pub display_content: Option<String>,
+  /// How sure the rater is of `value`, 0..1, in the same format as `value`
+  pub confidence: Option<String>,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum DecodeError {
+  /// Tag does not start with `Ŧ`; probably another app's link
+  MissingHeader,
+  /// `Ŧ` is not followed by `→` or `↩`
+  UnknownDirection,
+  /// Version byte of a newer format than this codec knows about
+  UnsupportedVersion(u8),
+  InvalidUtf8,
+  /// A trust atom tag has from 2 (content and value) to 6 chunks
+  ChunkCount(usize),
+  /// Flags or fixed-width fields after the content of a compact tag don't match up
+  CompactFields,
+}
+
+impl fmt::Display for DecodeError {
+  fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+    match self {
+      Self::MissingHeader => write!(f, "Link tag does not start with the trust atom header `Ŧ`"),
+      Self::UnknownDirection => write!(f, "Link tag header is not followed by `→` or `↩`"),
+      Self::UnsupportedVersion(version) => write!(
+        f,
+        "Link tag format version {version} is not supported; maybe upgrade trust_atom_codec?"
+      ),
+      Self::InvalidUtf8 => write!(f, "Link tag is not valid UTF-8"),
+      Self::ChunkCount(found) => write!(
+        f,
+        "Link tag must have from 2 to {CHUNK_COUNT} NUL separated chunks, but found {found}"
+      ),
+      Self::CompactFields => write!(f, "Compact link tag has malformed fields after its content"),
+    }
+  }
+}
+
+impl std::error::Error for DecodeError {}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum TagField {
+  Content,
+  Value,
+  Confidence,
+  /// The whole encoded tag
+  Tag,
+}
+
+impl fmt::Display for TagField {
+  fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+    match self {
+      Self::Content => write!(f, "Content"),
+      Self::Value => write!(f, "Value"),
+      Self::Confidence => write!(f, "Confidence"),
+      Self::Tag => write!(f, "Link tag"),
+    }
+  }
+}
+
+/// `size` and `limit` are in bytes, except for `TagField::Value` and `TagField::Confidence`, which
+/// are in characters
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct TagTooLarge {
+  pub field: TagField,
+  pub size: usize,
+  pub limit: usize,
+}
+
+impl fmt::Display for TagTooLarge {
+  fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+    let unit = match self.field {
+      TagField::Value | TagField::Confidence => "characters",
+      TagField::Content | TagField::Tag => "bytes",
+    };
+    write!(
+      f,
+      "{} is too large: {} {unit}, but the limit is {} {unit}",
+      self.field, self.size, self.limit
+    )
+  }
+}
+
+impl std::error::Error for TagTooLarge {}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum EncodeError {
+  TagTooLarge(TagTooLarge),
+  /// NUL separates chunks, so content or value containing it would corrupt the chunks after it
+  ContainsNul(TagField),
+  /// Compact tags only: value or confidence not a decimal in the range -1..1
+  InvalidValue(String),
+  /// Compact tags only: not up to 9 digits
+  InvalidBucket(String),
+  /// Compact tags only: not a base64 `EntryHash`
+  InvalidExtraHash(String),
+}
+
+impl fmt::Display for EncodeError {
+  fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+    match self {
+      Self::TagTooLarge(error) => write!(f, "{error}"),
+      Self::ContainsNul(field) => write!(
+        f,
+        "{field} must not contain NUL characters (`\\u{{0}}`), which separate link tag chunks"
+      ),
+      Self::InvalidValue(value) => {
+        write!(
+          f,
+          "Value must be a decimal in the range -1..1, but got: `{value}`"
+        )
+      }
+      Self::InvalidBucket(bucket) => {
+        write!(f, "Bucket must be up to 9 digits, but got: `{bucket}`")
+      }
+      Self::InvalidExtraHash(extra_hash) => {
+        write!(
+          f,
+          "Extra hash must be a base64 `EntryHash`, but got: `{extra_hash}`"
+        )
+      }
+    }
+  }
+}
+
+impl std::error::Error for EncodeError {}
+
+impl From<TagTooLarge> for EncodeError {
+  fn from(error: TagTooLarge) -> Self {
+    Self::TagTooLarge(error)
+  }
+}
+
+/// `Ŧ→` or `Ŧ↩`, without a version byte; on its own, a tag prefix matching every trust atom in
+/// that direction, in every version
+#[must_use]
+pub fn header(direction: Direction) -> Vec<u8> {
+  let arrow = match direction {
+    Direction::Forward => LINK_TAG_ARROW_FORWARD,
+    Direction::Reverse => LINK_TAG_ARROW_REVERSE,
+  };
+
+  let mut bytes = vec![];
+  bytes.extend_from_slice(&LINK_TAG_HEADER);
+  bytes.extend_from_slice(&arrow);
+  bytes
+}
+
+/// Fails if the tag is over the size limits, or (for compact tags) its fields don't parse into
+/// binary
+pub fn encode(tag: &TrustAtomTag) -> Result<Vec<u8>, EncodeError> {
+  check_no_nul(
+    TagField::Content,
+    tag.content.as_deref().unwrap_or_default(),
+  )?;
+  check_no_nul(TagField::Value, tag.value.as_deref().unwrap_or_default())?;
+  check_no_nul(
+    TagField::Content,
+    tag.display_content.as_deref().unwrap_or_default(),
+  )?;
+  check_no_nul(
+    TagField::Confidence,
+    tag.confidence.as_deref().unwrap_or_default(),
+  )?;
+  check_field_sizes(tag)?;
+  let content = tag.content.as_deref().unwrap_or_default();
+  let bytes = match tag.version {
+    TagVersion::V1 | TagVersion::V2 => {
+      let mut chunks = vec![
+        content,
+        tag.value.as_deref().unwrap_or_default(),
+        tag.bucket.as_deref().unwrap_or_default(),
+        tag.extra_hash.as_deref().unwrap_or_default(),
+      ];
+      match (&tag.display_content, &tag.confidence) {
+        (display_content, Some(confidence)) => {
+          chunks.push(display_content.as_deref().unwrap_or_default());
+          chunks.push(confidence);
+        }
+        (Some(display_content), None) => chunks.push(display_content),
+        (None, None) => {}
+      }
+      encode_prefix(tag.direction, tag.version, &chunks)
+    }
+    TagVersion::Compact => {
+      let mut bytes = encode_prefix(tag.direction, tag.version, &[content, ""]);
+      bytes.extend(compact::encode_fields(
+        tag.value.as_deref(),
+        tag.bucket.as_deref(),
+        tag.extra_hash.as_deref(),
+        tag.confidence.as_deref(),
+        tag.display_content.as_deref(),
+      )?);
+      bytes
+    }
+  };
+  check_tag_size(bytes.len())?;
+  Ok(bytes)
+}
+
+/// Size limits on an encoded tag, eg for validating links written by other agents.  Content and
+/// value limits are only checked if the tag decodes.
+pub fn check_size(bytes: &[u8]) -> Result<(), TagTooLarge> {
+  check_tag_size(bytes.len())?;
+  match decode(bytes) {
+    Ok(tag) => check_field_sizes(&tag),
+    Err(_) => Ok(()),
+  }
+}
+
+/// Content and value filters of a query must pass this too, or they could never match
+pub fn check_no_nul(field: TagField, text: &str) -> Result<(), EncodeError> {
+  if text.contains(UNICODE_NUL_STR) {
+    Err(EncodeError::ContainsNul(field))
+  } else {
+    Ok(())
+  }
+}
+
+fn check_tag_size(size: usize) -> Result<(), TagTooLarge> {
+  check_limit(TagField::Tag, size, MAX_TAG_BYTES)
+}
+
+fn check_field_sizes(tag: &TrustAtomTag) -> Result<(), TagTooLarge> {
+  for content in [&tag.content, &tag.display_content].into_iter().flatten() {
+    check_limit(TagField::Content, content.len(), MAX_CONTENT_BYTES)?;
+  }
+  if let Some(value) = &tag.value {
+    check_limit(TagField::Value, value.chars().count(), MAX_VALUE_CHARS)?;
+  }
+  if let Some(confidence) = &tag.confidence {
+    check_limit(
+      TagField::Confidence,
+      confidence.chars().count(),
+      MAX_VALUE_CHARS,
+    )?;
+  }
+  Ok(())
+}
+
+fn check_limit(field: TagField, size: usize, limit: usize) -> Result<(), TagTooLarge> {
+  if size > limit {
+    Err(TagTooLarge { field, size, limit })
+  } else {
+    Ok(())
+  }
+}
+
+/// Header and version byte followed by the leading `chunks`, for prefix searches on link tags, eg
+/// `encode_prefix(Direction::Forward, TagVersion::V2, &["sushi", ""])` matches content of exactly
+/// `sushi`.
+///
+/// Compact tags only hold content as text, so for them chunks after the content are ignored, other
+/// than to end the content with NUL.
+#[must_use]
+pub fn encode_prefix(direction: Direction, version: TagVersion, chunks: &[&str]) -> Vec<u8> {
+  let mut bytes = header(direction);
+  bytes.extend(version.byte());
+  match chunks {
+    [content, _, ..] if version == TagVersion::Compact => {
+      bytes.extend_from_slice(content.as_bytes());
+      bytes.extend_from_slice(UNICODE_NUL_STR.as_bytes());
+    }
+    _ => bytes.extend_from_slice(chunks.join(UNICODE_NUL_STR).as_bytes()),
+  }
+  bytes
+}
+
+pub fn decode(bytes: &[u8]) -> Result<TrustAtomTag, DecodeError> {
+  let rest = bytes
+    .strip_prefix(&LINK_TAG_HEADER)
+    .ok_or(DecodeError::MissingHeader)?;
+  let (direction, rest) = if let Some(rest) = rest.strip_prefix(&LINK_TAG_ARROW_FORWARD) {
+    (Direction::Forward, rest)
+  } else if let Some(rest) = rest.strip_prefix(&LINK_TAG_ARROW_REVERSE) {
+    (Direction::Reverse, rest)
+  } else {
+    return Err(DecodeError::UnknownDirection);
+  };
+  match rest.split_first() {
+    Some((&byte, versioned)) if byte < 0x20 && byte != 0 => {
+      let decoded = match byte {
+        2 => decode_chunks(direction, TagVersion::V2, versioned),
+        3 => decode_compact(direction, versioned),
+        _ => Err(DecodeError::UnsupportedVersion(byte)),
+      };
+      // Version 1 content starting with a control character
+      decoded.or_else(|error| decode_chunks(direction, TagVersion::V1, rest).map_err(|_| error))
+    }
+    _ => decode_chunks(direction, TagVersion::V1, rest),
+  }
+}
+
+fn decode_chunks(
+  direction: Direction,
+  version: TagVersion,
+  rest: &[u8],
+) -> Result<TrustAtomTag, DecodeError> {
+  let rest = std::str::from_utf8(rest).map_err(|_| DecodeError::InvalidUtf8)?;
+  let chunks: Vec<&str> = rest.split(UNICODE_NUL_STR).collect();
+  if chunks.len() < 2 || chunks.len() > CHUNK_COUNT {
+    return Err(DecodeError::ChunkCount(chunks.len()));
+  }
+  let chunk = |index: usize| {
+    chunks
+      .get(index)
+      .filter(|chunk| !chunk.is_empty())
+      .map(ToString::to_string)
+  };
+
+  Ok(TrustAtomTag {
+    direction,
+    version,
+    content: chunk(0),
+    value: chunk(1),
+    bucket: chunk(2),
+    extra_hash: chunk(3),
+    display_content: chunk(4),
+    confidence: chunk(5),
+  })
+}
+
+fn decode_compact(direction: Direction, bytes: &[u8]) -> Result<TrustAtomTag, DecodeError> {
+  let content_len = bytes
+    .iter()
+    .position(|&byte| byte == 0)
+    .ok_or(DecodeError::ChunkCount(1))?;
+  let content = std::str::from_utf8(&bytes[..content_len]).map_err(|_| DecodeError::InvalidUtf8)?;
+  let fields = compact::decode_fields(&bytes[content_len + 1..])?;
+
+  Ok(TrustAtomTag {
+    direction,
+    version: TagVersion::Compact,
+    content: Some(content.to_string()).filter(|content| !content.is_empty()),
+    value: fields.value,
+    bucket: fields.bucket,
+    extra_hash: fields.extra_hash,
+    display_content: fields.display_content,
+    confidence: fields.confidence,
+  })
+}
+
+#[cfg(test)]
+#[allow(clippy::unwrap_used)]
+#[allow(non_snake_case)]
+mod tests {
+
+  use super::*;
+
+  fn tag() -> TrustAtomTag {
+    TrustAtomTag {
+      direction: Direction::Forward,
+      version: TagVersion::V2,
+      content: Some("sushi".to_string()),
+      value: Some(".800000000".to_string()),
+      bucket: Some("901234567".to_string()),
+      extra_hash: Some("uhCEkBsnnW9JSVhGQx4AE2m0lSlWLrioEHP-7Uj4ZnbpW6TJz1B0D".to_string()),
+      display_content: None,
+      confidence: None,
+    }
+  }
+
+  #[test]
+  fn test_encode() {
+    assert_eq!(
+      String::from_utf8(encode(&tag()).unwrap()).unwrap(),
+      "Ŧ→\u{2}sushi\u{0}.800000000\u{0}901234567\u{0}uhCEkBsnnW9JSVhGQx4AE2m0lSlWLrioEHP-7Uj4ZnbpW6TJz1B0D"
+    );
+
+    let reverse = TrustAtomTag {
+      direction: Direction::Reverse,
+      version: TagVersion::V1,
+      content: None,
+      value: Some(".800000000".to_string()),
+      bucket: Some("901234567".to_string()),
+      extra_hash: None,
+      display_content: None,
+      confidence: None,
+    };
+    assert_eq!(
+      String::from_utf8(encode(&reverse).unwrap()).unwrap(),
+      "Ŧ↩\u{0}.800000000\u{0}901234567\u{0}"
+    );
+  }
+
+  #[test]
+  fn test_encode_prefix() {
+    assert_eq!(
+      encode_prefix(Direction::Reverse, TagVersion::V1, &[]),
+      header(Direction::Reverse)
+    );
+    assert_eq!(
+      String::from_utf8(encode_prefix(
+        Direction::Forward,
+        TagVersion::V2,
+        &["sushi", ""]
+      ))
+      .unwrap(),
+      "Ŧ→\u{2}sushi\u{0}"
+    );
+    assert!(encode(&tag()).unwrap().starts_with(&encode_prefix(
+      Direction::Forward,
+      TagVersion::V2,
+      &["sushi", ".8"]
+    )));
+    for version in TagVersion::ALL {
+      assert!(encode_prefix(Direction::Forward, version, &["sushi"])
+        .starts_with(&header(Direction::Forward)));
+    }
+  }
+
+  #[test]
+  fn test_decode__round_trip() {
+    assert_eq!(decode(&encode(&tag()).unwrap()).unwrap(), tag());
+
+    let v1 = TrustAtomTag {
+      version: TagVersion::V1,
+      ..tag()
+    };
+    assert_eq!(decode(&encode(&v1).unwrap()).unwrap(), v1);
+    assert_eq!(
+      decode("Ŧ→sushi\u{0}.800000000".as_bytes()).unwrap().version,
+      TagVersion::V1
+    );
+
+    let empty = TrustAtomTag {
+      direction: Direction::Reverse,
+      version: TagVersion::V2,
+      content: None,
+      value: None,
+      bucket: None,
+      extra_hash: None,
+      display_content: None,
+      confidence: None,
+    };
+    assert_eq!(decode(&encode(&empty).unwrap()).unwrap(), empty);
+  }
+
+  #[test]
+  fn test_encode__size_limits() {
+    let max_content = TrustAtomTag {
+      content: Some("a".repeat(MAX_CONTENT_BYTES)),
+      ..tag()
+    };
+    check_size(&encode(&max_content).unwrap()).unwrap();
+
+    // multi-byte characters count by their UTF-8 length
+    let long_content = TrustAtomTag {
+      content: Some("é".repeat(MAX_CONTENT_BYTES / 2 + 1)),
+      ..tag()
+    };
+    assert_eq!(
+      encode(&long_content),
+      Err(EncodeError::TagTooLarge(TagTooLarge {
+        field: TagField::Content,
+        size: MAX_CONTENT_BYTES + 2,
+        limit: MAX_CONTENT_BYTES,
+      }))
+    );
+
+    let long_value = TrustAtomTag {
+      value: Some(format!("-.{}1", "0".repeat(MAX_VALUE_CHARS))),
+      ..tag()
+    };
+    assert_eq!(
+      encode(&long_value),
+      Err(EncodeError::TagTooLarge(TagTooLarge {
+        field: TagField::Value,
+        size: MAX_VALUE_CHARS + 3,
+        limit: MAX_VALUE_CHARS,
+      }))
+    );
+
+    let long_tag = TrustAtomTag {
+      content: Some("a".repeat(MAX_CONTENT_BYTES)),
+      bucket: Some("1".repeat(100)),
+      ..tag()
+    };
+    let long_tag_size = encode_prefix(
+      Direction::Forward,
+      TagVersion::V2,
+      &[
+        &"a".repeat(MAX_CONTENT_BYTES),
+        ".800000000",
+        &"1".repeat(100),
+        long_tag.extra_hash.as_deref().unwrap(),
+      ],
+    )
+    .len();
+    assert_eq!(
+      encode(&long_tag),
+      Err(EncodeError::TagTooLarge(TagTooLarge {
+        field: TagField::Tag,
+        size: long_tag_size,
+        limit: MAX_TAG_BYTES,
+      }))
+    );
+  }
+
+  #[test]
+  fn test_encode__nul() {
+    for content in [
+      "\u{0}",
+      "sushi\u{0}",
+      "\u{0}sushi",
+      "sushi\u{0}.999999999\u{0}1\u{0}",
+    ] {
+      for version in TagVersion::ALL {
+        let adversarial = TrustAtomTag {
+          version,
+          content: Some(content.to_string()),
+          ..tag()
+        };
+        assert_eq!(
+          encode(&adversarial),
+          Err(EncodeError::ContainsNul(TagField::Content)),
+          "{content:?}"
+        );
+      }
+    }
+
+    let nul_value = TrustAtomTag {
+      value: Some(".8\u{0}".to_string()),
+      ..tag()
+    };
+    assert_eq!(
+      encode(&nul_value),
+      Err(EncodeError::ContainsNul(TagField::Value))
+    );
+  }
+
+  #[test]
+  fn test_decode__adversarial_content() {
+    // headers, arrows, version bytes and other control characters are just content after the
+    // version byte
+    for content in [
+      "Ŧ→",
+      "Ŧ↩\u{2}sushi",
+      "\u{2}",
+      "\u{3}\u{1}",
+      "\u{7f}",
+      "\u{feff}",
+    ] {
+      for version in [TagVersion::V2, TagVersion::Compact] {
+        let adversarial = TrustAtomTag {
+          version,
+          content: Some(content.to_string()),
+          ..tag()
+        };
+        assert_eq!(
+          decode(&encode(&adversarial).unwrap()).unwrap(),
+          adversarial,
+          "{content:?}"
+        );
+      }
+    }
+  }
+
+  #[test]
+  fn test_display_content() {
+    for version in TagVersion::ALL {
+      let with_display_content = TrustAtomTag {
+        version,
+        content: Some("cafe\u{301}".to_string()),
+        display_content: Some("Cafe\u{301} ".to_string()),
+        ..tag()
+      };
+      let bytes = encode(&with_display_content).unwrap();
+      assert_eq!(decode(&bytes).unwrap(), with_display_content, "{version:?}");
+      // searches match on the normalized content
+      assert!(bytes.starts_with(&encode_prefix(
+        Direction::Forward,
+        version,
+        &["cafe\u{301}"]
+      )));
+    }
+
+    let nul_display_content = TrustAtomTag {
+      display_content: Some("Sushi\u{0}".to_string()),
+      ..tag()
+    };
+    assert_eq!(
+      encode(&nul_display_content),
+      Err(EncodeError::ContainsNul(TagField::Content))
+    );
+
+    // tags without display content are unchanged
+    assert_eq!(encode(&tag()).unwrap().split(|&byte| byte == 0).count(), 4);
+  }
+
+  #[test]
+  fn test_confidence() {
+    for version in TagVersion::ALL {
+      for display_content in [None, Some("Sushi".to_string())] {
+        let with_confidence = TrustAtomTag {
+          version,
+          display_content,
+          confidence: Some(".500000000".to_string()),
+          ..tag()
+        };
+        assert_eq!(
+          decode(&encode(&with_confidence).unwrap()).unwrap(),
+          with_confidence,
+          "{version:?}"
+        );
+      }
+    }
+
+    let nul_confidence = TrustAtomTag {
+      confidence: Some(".5\u{0}".to_string()),
+      ..tag()
+    };
+    assert_eq!(
+      encode(&nul_confidence),
+      Err(EncodeError::ContainsNul(TagField::Confidence))
+    );
+    let compact_invalid_confidence = TrustAtomTag {
+      version: TagVersion::Compact,
+      confidence: Some("sure".to_string()),
+      ..tag()
+    };
+    assert_eq!(
+      encode(&compact_invalid_confidence),
+      Err(EncodeError::InvalidValue("sure".to_string()))
+    );
+  }
+
+  #[test]
+  fn test_check_size() {
+    check_size(&encode(&tag()).unwrap()).unwrap();
+    check_size(&vec![b'a'; MAX_TAG_BYTES]).unwrap();
+    assert_eq!(
+      check_size(&vec![b'a'; MAX_TAG_BYTES + 1])
+        .unwrap_err()
+        .field,
+      TagField::Tag
+    );
+    let long_content = encode_prefix(
+      Direction::Reverse,
+      TagVersion::V2,
+      &[&"a".repeat(MAX_CONTENT_BYTES + 1), ".8"],
+    );
+    assert_eq!(
+      check_size(&long_content).unwrap_err().field,
+      TagField::Content
+    );
+  }
+
+  #[test]
+  fn test_compact() {
+    let compact = TrustAtomTag {
+      version: TagVersion::Compact,
+      ..tag()
+    };
+    let bytes = encode(&compact).unwrap();
+    assert_eq!(decode(&bytes).unwrap(), compact);
+    assert!(bytes.len() < encode(&tag()).unwrap().len());
+
+    // content is still prefix searchable
+    assert!(bytes.starts_with(&encode_prefix(
+      Direction::Forward,
+      TagVersion::Compact,
+      &["sushi", ".8"]
+    )));
+    assert!(bytes.starts_with(&encode_prefix(
+      Direction::Forward,
+      TagVersion::Compact,
+      &["sus"]
+    )));
+    assert!(!bytes.starts_with(&encode_prefix(
+      Direction::Forward,
+      TagVersion::Compact,
+      &["sus", ""]
+    )));
+
+    let empty = TrustAtomTag {
+      direction: Direction::Reverse,
+      version: TagVersion::Compact,
+      content: None,
+      value: None,
+      bucket: None,
+      extra_hash: None,
+      display_content: None,
+      confidence: None,
+    };
+    assert_eq!(decode(&encode(&empty).unwrap()).unwrap(), empty);
+  }
+
+  #[test]
+  fn test_compact__errors() {
+    let invalid_value = TrustAtomTag {
+      version: TagVersion::Compact,
+      value: Some("lots".to_string()),
+      ..tag()
+    };
+    assert_eq!(
+      encode(&invalid_value),
+      Err(EncodeError::InvalidValue("lots".to_string()))
+    );
+    let invalid_extra_hash = TrustAtomTag {
+      version: TagVersion::Compact,
+      extra_hash: Some("uhCEk".to_string()),
+      ..tag()
+    };
+    assert_eq!(
+      encode(&invalid_extra_hash),
+      Err(EncodeError::InvalidExtraHash("uhCEk".to_string()))
+    );
+
+    let compact = TrustAtomTag {
+      version: TagVersion::Compact,
+      ..tag()
+    };
+    let bytes = encode(&compact).unwrap();
+    assert_eq!(
+      decode(&bytes[..bytes.len() - 1]),
+      Err(DecodeError::CompactFields)
+    );
+    assert_eq!(
+      decode(&[&bytes[..], &[0]].concat()),
+      Err(DecodeError::CompactFields)
+    );
+    assert_eq!(
+      decode(&encode_prefix(
+        Direction::Forward,
+        TagVersion::Compact,
+        &["sushi"]
+      )),
+      Err(DecodeError::ChunkCount(1))
+    );
+  }
+
+  #[test]
+  fn test_encode__small_and_negative_values() {
+    // canonical forms of eg -0.001, 0.0001 and the smallest value with 9 significant figures
+    for value in [
+      "-.00100000000",
+      ".000100000000",
+      "-.0000000000000000000123456789",
+    ] {
+      let small = TrustAtomTag {
+        value: Some(value.to_string()),
+        confidence: Some(value.trim_start_matches('-').to_string()),
+        ..tag()
+      };
+      assert_eq!(decode(&encode(&small).unwrap()).unwrap(), small, "{value}");
+    }
+  }
+
+  #[test]
+  fn test_decode__v1_content_starting_with_control_character() {
+    for content in ["\u{7}sushi", "\u{3}sushi", "\u{1f}"] {
+      let v1 = TrustAtomTag {
+        version: TagVersion::V1,
+        content: Some(content.to_string()),
+        ..tag()
+      };
+      assert_eq!(decode(&encode(&v1).unwrap()).unwrap(), v1, "{content:?}");
+    }
+
+    // also a valid version 2 tag, so it is read as one
+    let ambiguous = TrustAtomTag {
+      version: TagVersion::V1,
+      content: Some("\u{2}sushi".to_string()),
+      ..tag()
+    };
+    let decoded = decode(&encode(&ambiguous).unwrap()).unwrap();
+    assert_eq!(decoded.version, TagVersion::V2);
+    assert_eq!(decoded.content, Some("sushi".to_string()));
+  }
+
+  #[test]
+  fn test_decode__errors() {
+    assert_eq!(decode(b"sushi"), Err(DecodeError::MissingHeader));
+    assert_eq!(decode(&LINK_TAG_HEADER), Err(DecodeError::UnknownDirection));
+    // not a version 1 tag either, with a single chunk
+    assert_eq!(
+      decode("Ŧ→\u{7}sushi".as_bytes()),
+      Err(DecodeError::UnsupportedVersion(7))
+    );
+    assert_eq!(
+      decode(&[&header(Direction::Forward)[..], &[0xff]].concat()),
+      Err(DecodeError::InvalidUtf8)
+    );
+    assert_eq!(
+      decode(&encode_prefix(
+        Direction::Forward,
+        TagVersion::V2,
+        &["sushi"]
+      )),
+      Err(DecodeError::ChunkCount(1))
+    );
+    assert_eq!(
+      decode(&encode_prefix(
+        Direction::Forward,
+        TagVersion::V2,
+        &["a", "b", "c", "d", "e", "f", "g"]
+      )),
+      Err(DecodeError::ChunkCount(7))
+    );
+  }
+}
@@ -1,10 +1,30 @@
 use hdi::prelude::*;
 use std::collections::BTreeMap;
+use trust_atom_types::RollupScore;
 
+/// Free-form text target without a namespace, in the normalized form produced by
+/// `trust_atom_types::string_target`.  Same shape as before namespaces, so existing string targets
+/// keep their entry hashes.
 #[hdk_entry_helper]
 #[derive(Clone)]
 pub struct StringTarget(pub String);
 
+/// As `StringTarget`, in a namespace (eg `isbn`)
+#[hdk_entry_helper]
+#[derive(Clone)]
+pub struct NamespacedStringTarget {
+  pub namespace: String,
+  pub text: String,
+}
+
+/// URI (or IPFS CID etc) of an external resource, stored so that its `ExternalHash` can be resolved
+#[hdk_entry_helper]
+#[derive(Clone)]
+pub struct ExternalTarget {
+  pub uri: String,
+}
+
+/// Only created by the test helpers, but kept in every build for its entry def index
 #[hdk_entry_helper]
 #[derive(Clone)]
 pub struct Example {
@@ -17,6 +37,24 @@ pub struct Extra {
   pub fields: BTreeMap<String, String>,
 }
 
+/// Materialized rollup, keyed by seed agent plus rollup parameters (`cache_key`)
+#[hdk_entry_helper]
+#[derive(Clone)]
+pub struct CachedRollup {
+  pub seed: AnyLinkableHash,
+  pub cache_key: String,
+  /// Hash over the create-link hashes of the seed's trust atoms when the rollup was computed,
+  /// followed by the same over the caller's for rollups weighted by the caller's TrustGraph
+  pub first_hop_fingerprint: Vec<u8>,
+  pub computed_at: Timestamp,
+  pub scores: Vec<RollupScore>,
+}
+
+#[hdk_entry_helper]
+#[derive(Clone)]
+pub struct PrivateCachedRollup(pub CachedRollup);
+
+// Entry def indices are part of the DNA, so new entry types go at the end
 #[hdk_entry_defs]
 #[unit_enum(UnitEntryTypes)]
 pub enum EntryTypes {
@@ -26,4 +64,12 @@ pub enum EntryTypes {
   StringTarget(StringTarget),
   #[entry_def]
   Extra(Extra),
+  #[entry_def]
+  CachedRollup(CachedRollup),
+  #[entry_def(visibility = "private")]
+  PrivateCachedRollup(PrivateCachedRollup),
+  #[entry_def]
+  ExternalTarget(ExternalTarget),
+  #[entry_def]
+  NamespacedStringTarget(NamespacedStringTarget),
 }
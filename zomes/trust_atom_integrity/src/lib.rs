@@ -7,4 +7,11 @@ pub mod validation;
 #[hdk_link_types]
 pub enum LinkTypes {
   TrustAtom,
+  /// From an `ExternalHash` to the `ExternalTarget` entry holding its URI
+  ExternalTarget,
+}
+
+#[hdk_extern]
+pub fn validate(op: Op) -> ExternResult<ValidateCallbackResult> {
+  validation::validate(&op)
 }
@@ -1 +1,70 @@
+use hdi::prelude::*;
+use trust_atom_codec::{check_size, decode};
+use trust_atom_types::uri::external_hash_for_uri;
 
+use crate::entries::{EntryTypes, ExternalTarget};
+use crate::LinkTypes;
+
+pub fn validate(op: &Op) -> ExternResult<ValidateCallbackResult> {
+  match op.to_type::<EntryTypes, LinkTypes>()? {
+    OpType::RegisterCreateLink {
+      tag,
+      link_type: LinkTypes::TrustAtom,
+      ..
+    } => validate_trust_atom_tag(&tag),
+    OpType::RegisterCreateLink {
+      base_address,
+      target_address,
+      link_type: LinkTypes::ExternalTarget,
+      ..
+    } => validate_external_target_link(&base_address, target_address),
+    _ => Ok(ValidateCallbackResult::Valid),
+  }
+}
+
+/// Trust atom links must have tags which decode (so no NUL inside content or value, which would
+/// shift the chunks after it) and keep to the tag size limits.  Host zomes storing trust atoms
+/// under their own link types can call this from their own `validate` callback.
+pub fn validate_trust_atom_tag(tag: &LinkTag) -> ExternResult<ValidateCallbackResult> {
+  if let Err(error) = decode(&tag.0) {
+    return Ok(ValidateCallbackResult::Invalid(format!(
+      "Invalid trust atom link tag: {error}"
+    )));
+  }
+  match check_size(&tag.0) {
+    Ok(()) => Ok(ValidateCallbackResult::Valid),
+    Err(error) => Ok(ValidateCallbackResult::Invalid(error.to_string())),
+  }
+}
+
+/// External target links must go from the `ExternalHash` of a URI to an `ExternalTarget` entry
+/// holding that URI, so that nobody can attach another URI to a hash
+fn validate_external_target_link(
+  base_address: &AnyLinkableHash,
+  target_address: AnyLinkableHash,
+) -> ExternResult<ValidateCallbackResult> {
+  let entry_hash = match target_address.into_primitive() {
+    AnyLinkablePrimitive::Entry(entry_hash) => entry_hash,
+    _ => {
+      return Ok(ValidateCallbackResult::Invalid(
+        "External target link must point to an `ExternalTarget` entry".to_string(),
+      ))
+    }
+  };
+  let external_target = match ExternalTarget::try_from(must_get_entry(entry_hash)?.content) {
+    Ok(external_target) => external_target,
+    Err(_) => {
+      return Ok(ValidateCallbackResult::Invalid(
+        "External target link must point to an `ExternalTarget` entry".to_string(),
+      ))
+    }
+  };
+  if *base_address == AnyLinkableHash::from(external_hash_for_uri(&external_target.uri)) {
+    Ok(ValidateCallbackResult::Valid)
+  } else {
+    Ok(ValidateCallbackResult::Invalid(format!(
+      "External target link base is not the hash of its URI: `{}`",
+      external_target.uri
+    )))
+  }
+}
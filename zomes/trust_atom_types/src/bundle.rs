@@ -0,0 +1,240 @@
+//! Portable, signed export of one agent's trust atoms.
+//!
+//! The bundle payload is JSON, made of trust atoms in the `trust_atom_json` format plus their
+//! creation timestamps.  The signature is a plain Ed25519 signature of the payload's UTF-8 bytes
+//! by the exporting agent, so it can be checked by any Ed25519 library against the 32 byte
+//! public key inside the agent's `AgentPubKey` (`get_raw_32`), without a Holochain conductor:
+//!
+//! ```json
+//! {
+//!   "version": "trustgraph/trust_atom_bundle/1",
+//!   "agent": "hc:agent:uhCAk…",
+//!   "exported_at": 1678312691145941,
+//!   "trust_atoms": [
+//!     {
+//!       "version": "trustgraph/trust_atom/1",
+//!       "source": "hc:agent:uhCAk…",
+//!       "target": "hc:entry:uhCEk…",
+//!       "content": "sushi",
+//!       "value": 0.8,
+//!       "extra": { "details": "Excellent specials" },
+//!       "timestamp": 1678312600000000
+//!     }
+//!   ]
+//! }
+//! ```
+
+#![allow(clippy::module_name_repetitions)]
+
+use crate::trust_atom_json::{TrustAtomJson, TrustAtomJsonError};
+use crate::uri::{agent_uri, hash_from_uri};
+use crate::{TimestampedTrustAtom, TrustAtom};
+use hdk::prelude::*;
+use std::fmt;
+
+pub const TRUST_ATOM_BUNDLE_VERSION: &str = "trustgraph/trust_atom_bundle/1";
+
+#[derive(Serialize, Deserialize, SerializedBytes, Debug, Clone, PartialEq, Eq)]
+pub struct TrustAtomBundle {
+  /// JSON `TrustAtomBundlePayload`: exactly the bytes that were signed
+  pub payload: String,
+  /// Ed25519 signature of `payload` by the exporting agent
+  pub signature: Signature,
+}
+
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq)]
+pub struct TrustAtomBundlePayload {
+  pub version: String,
+  pub agent: String,
+  /// Microseconds since the Unix epoch
+  pub exported_at: i64,
+  pub trust_atoms: Vec<BundledTrustAtom>,
+}
+
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq)]
+pub struct BundledTrustAtom {
+  #[serde(flatten)]
+  pub trust_atom: TrustAtomJson,
+  /// Microseconds since the Unix epoch
+  pub timestamp: i64,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum TrustAtomBundleError {
+  Json(String),
+  UnsupportedVersion(String),
+  TrustAtom(TrustAtomJsonError),
+  /// A trust atom whose source is not the bundle's agent
+  ForeignTrustAtom(String),
+}
+
+impl fmt::Display for TrustAtomBundleError {
+  fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+    match self {
+      Self::Json(error) => write!(f, "Invalid TrustAtom bundle JSON: {error}"),
+      Self::UnsupportedVersion(version) => write!(
+        f,
+        "Unsupported TrustAtom bundle version: `{version}` (supported: `{TRUST_ATOM_BUNDLE_VERSION}`)"
+      ),
+      Self::TrustAtom(error) => write!(f, "{error}"),
+      Self::ForeignTrustAtom(source) => write!(
+        f,
+        "Bundle contains a trust atom created by another agent: `{source}`"
+      ),
+    }
+  }
+}
+
+impl std::error::Error for TrustAtomBundleError {}
+
+impl From<TrustAtomJsonError> for TrustAtomBundleError {
+  fn from(error: TrustAtomJsonError) -> Self {
+    Self::TrustAtom(error)
+  }
+}
+
+impl TrustAtomBundlePayload {
+  #[must_use]
+  pub fn new(
+    agent: &AgentPubKey,
+    exported_at: Timestamp,
+    trust_atoms: &[TimestampedTrustAtom],
+  ) -> Self {
+    Self {
+      version: TRUST_ATOM_BUNDLE_VERSION.to_string(),
+      agent: agent_uri(&AnyLinkableHash::from(agent.clone())),
+      exported_at: exported_at.as_micros(),
+      trust_atoms: trust_atoms
+        .iter()
+        .map(|timestamped| BundledTrustAtom {
+          trust_atom: TrustAtomJson::from(&timestamped.trust_atom),
+          timestamp: timestamped.timestamp.as_micros(),
+        })
+        .collect(),
+    }
+  }
+
+  pub fn to_json_string(&self) -> Result<String, TrustAtomBundleError> {
+    serde_json::to_string(self).map_err(|error| TrustAtomBundleError::Json(error.to_string()))
+  }
+
+  pub fn from_json_str(json: &str) -> Result<Self, TrustAtomBundleError> {
+    let payload: Self =
+      serde_json::from_str(json).map_err(|error| TrustAtomBundleError::Json(error.to_string()))?;
+    if payload.version == TRUST_ATOM_BUNDLE_VERSION {
+      Ok(payload)
+    } else {
+      Err(TrustAtomBundleError::UnsupportedVersion(payload.version))
+    }
+  }
+
+  /// The agent whose key must have signed the bundle
+  pub fn signer(&self) -> Result<AgentPubKey, TrustAtomBundleError> {
+    let agent = hash_from_uri(&self.agent).map_err(TrustAtomJsonError::from)?;
+    Ok(AgentPubKey::from_raw_36(agent.get_raw_36().to_vec()))
+  }
+
+  /// All trust atoms, which must have been created by the bundle's agent
+  pub fn trust_atoms(&self) -> Result<Vec<TimestampedTrustAtom>, TrustAtomBundleError> {
+    self
+      .trust_atoms
+      .iter()
+      .map(|bundled| {
+        if bundled.trust_atom.source != self.agent {
+          return Err(TrustAtomBundleError::ForeignTrustAtom(
+            bundled.trust_atom.source.clone(),
+          ));
+        }
+        Ok(TimestampedTrustAtom {
+          trust_atom: TrustAtom::try_from(&bundled.trust_atom)?,
+          timestamp: Timestamp::from_micros(bundled.timestamp),
+        })
+      })
+      .collect()
+  }
+}
+
+#[cfg(test)]
+#[allow(clippy::unwrap_used)]
+#[allow(non_snake_case)]
+mod tests {
+
+  use super::*;
+  use std::collections::BTreeMap;
+
+  fn agent() -> AgentPubKey {
+    AgentPubKey::from_raw_36(vec![1; 36])
+  }
+
+  fn timestamped_trust_atom() -> TimestampedTrustAtom {
+    TimestampedTrustAtom {
+      trust_atom: TrustAtom {
+        source_hash: AnyLinkableHash::from(agent()),
+        target_hash: AnyLinkableHash::from(EntryHash::from_raw_36(vec![2; 36])),
+        content: Some("sushi".to_string()),
+        display_content: None,
+        confidence: None,
+        value: Some(".800000000".to_string()),
+        extra: Some(BTreeMap::from([(
+          "details".to_string(),
+          "Excellent specials".to_string(),
+        )])),
+      },
+      timestamp: Timestamp::from_micros(1_678_312_600_000_000),
+    }
+  }
+
+  fn payload() -> TrustAtomBundlePayload {
+    TrustAtomBundlePayload::new(
+      &agent(),
+      Timestamp::from_micros(1_678_312_691_145_941),
+      &[timestamped_trust_atom()],
+    )
+  }
+
+  #[test]
+  fn test_payload__round_trip() {
+    let json = payload().to_json_string().unwrap();
+    let payload = TrustAtomBundlePayload::from_json_str(&json).unwrap();
+    assert_eq!(payload.signer().unwrap(), agent());
+    assert_eq!(payload.exported_at, 1_678_312_691_145_941);
+    assert_eq!(
+      payload.trust_atoms().unwrap(),
+      vec![timestamped_trust_atom()]
+    );
+  }
+
+  #[test]
+  fn test_payload__format() {
+    let json: serde_json::Value =
+      serde_json::from_str(&payload().to_json_string().unwrap()).unwrap();
+    assert_eq!(json["version"], TRUST_ATOM_BUNDLE_VERSION);
+    let trust_atom = &json["trust_atoms"][0];
+    assert_eq!(trust_atom["content"], "sushi");
+    assert_eq!(trust_atom["timestamp"], 1_678_312_600_000_000_i64);
+  }
+
+  #[test]
+  fn test_payload__foreign_trust_atom() {
+    let mut payload = payload();
+    payload.agent = agent_uri(&AnyLinkableHash::from(AgentPubKey::from_raw_36(vec![
+      3;
+      36
+    ])));
+    assert!(matches!(
+      payload.trust_atoms(),
+      Err(TrustAtomBundleError::ForeignTrustAtom(_))
+    ));
+  }
+
+  #[test]
+  fn test_payload__unsupported_version() {
+    let mut payload = payload();
+    payload.version = "trustgraph/trust_atom_bundle/99".to_string();
+    let json = payload.to_json_string().unwrap();
+    assert!(matches!(
+      TrustAtomBundlePayload::from_json_str(&json),
+      Err(TrustAtomBundleError::UnsupportedVersion(_))
+    ));
+  }
+}
@@ -0,0 +1,118 @@
+//! Normalization of trust atom content before it goes into link tags, so that eg `Sushi `,
+//! `sushi` and `café` typed with a combining accent match the same `content_full` and
+//! `content_starts_with` queries.
+
+#![allow(clippy::module_name_repetitions)]
+
+use serde::{Deserialize, Serialize};
+use unicode_normalization::UnicodeNormalization;
+
+/// Set under `content_normalization` in the DNA properties
+///
+/// Eg `content_normalization: { nfc: true, lowercase: true, trim: true }`.  Only `nfc` is on by
+/// default, as it never changes how content reads; the others are opt-in, as changing the policy
+/// of a DNA with existing trust atoms splits them from new ones.
+#[derive(Serialize, Deserialize, Debug, Clone, Copy, PartialEq, Eq)]
+#[serde(default)]
+pub struct ContentNormalization {
+  /// Unicode Normalization Form C, so precomposed and combining accents are the same
+  pub nfc: bool,
+  /// Unicode lowercase, so `Sushi` and `sushi` are the same
+  pub lowercase: bool,
+  /// Strip leading and trailing whitespace
+  pub trim: bool,
+}
+
+impl Default for ContentNormalization {
+  fn default() -> Self {
+    Self {
+      nfc: true,
+      lowercase: false,
+      trim: false,
+    }
+  }
+}
+
+impl ContentNormalization {
+  /// Form of `content` to store in link tags and to search for
+  ///
+  /// NFC comes before lowercasing, as Unicode case mapping is defined on composed text.
+  #[must_use]
+  pub fn normalize(&self, content: &str) -> String {
+    let mut normalized = if self.trim { content.trim() } else { content }.to_string();
+    if self.nfc {
+      normalized = normalized.nfc().collect();
+    }
+    if self.lowercase {
+      normalized = normalized.to_lowercase();
+    }
+    normalized
+  }
+}
+
+#[cfg(test)]
+#[allow(clippy::unwrap_used)]
+#[allow(non_snake_case)]
+mod tests {
+
+  use super::*;
+
+  const ALL: ContentNormalization = ContentNormalization {
+    nfc: true,
+    lowercase: true,
+    trim: true,
+  };
+
+  const NONE: ContentNormalization = ContentNormalization {
+    nfc: false,
+    lowercase: false,
+    trim: false,
+  };
+
+  #[test]
+  fn test_normalize() {
+    for content in ["Café", "Cafe\u{301}", "  CAFÉ\n", "cafe\u{301} "] {
+      assert_eq!(ALL.normalize(content), "caf\u{e9}", "{content:?}");
+    }
+  }
+
+  #[test]
+  fn test_normalize__each_setting() {
+    let content = " Cafe\u{301} ";
+    assert_eq!(NONE.normalize(content), content);
+    let nfc = ContentNormalization { nfc: true, ..NONE };
+    assert_eq!(nfc.normalize(content), " Caf\u{e9} ");
+    let lowercase = ContentNormalization {
+      lowercase: true,
+      ..NONE
+    };
+    assert_eq!(lowercase.normalize(content), " cafe\u{301} ");
+    let trim = ContentNormalization { trim: true, ..NONE };
+    assert_eq!(trim.normalize(content), "Cafe\u{301}");
+  }
+
+  #[test]
+  fn test_normalize__default() {
+    assert_eq!(
+      ContentNormalization::default().normalize(" Cafe\u{301} "),
+      " Caf\u{e9} "
+    );
+    let properties: ContentNormalization =
+      serde_json::from_str(r#"{ "lowercase": true }"#).unwrap();
+    assert_eq!(
+      properties,
+      ContentNormalization {
+        nfc: true,
+        lowercase: true,
+        trim: false,
+      }
+    );
+  }
+
+  #[test]
+  fn test_normalize__prefixes_stay_prefixes() {
+    assert!(ALL
+      .normalize("Sushi Bar")
+      .starts_with(&ALL.normalize("SUSHI")));
+  }
+}
@@ -0,0 +1,475 @@
+//! W3C Verifiable Credential (JSON-LD) representation of a trust atom, for partners outside Holochain.
+//!
+//! The issuer is the source agent as a `did:key` (Ed25519).  The proof is a Data Integrity proof
+//! using the `eddsa-jcs-2022` cryptosuite: an Ed25519 signature, by the agent's key, of
+//! `sha256(jcs(proof options)) || sha256(jcs(credential without proof))`, where `jcs` is the
+//! JSON Canonicalization Scheme (RFC 8785):
+//!
+//! ```json
+//! {
+//!   "@context": ["https://www.w3.org/ns/credentials/v2"],
+//!   "type": ["VerifiableCredential", "TrustAtomCredential"],
+//!   "issuer": "did:key:z6Mk…",
+//!   "credentialSubject": {
+//!     "id": "hc:entry:uhCEk…",
+//!     "content": "sushi",
+//!     "value": 0.8,
+//!     "extra": { "details": "Excellent specials" }
+//!   },
+//!   "proof": {
+//!     "type": "DataIntegrityProof",
+//!     "cryptosuite": "eddsa-jcs-2022",
+//!     "verificationMethod": "did:key:z6Mk…#z6Mk…",
+//!     "proofPurpose": "assertionMethod",
+//!     "proofValue": "z…"
+//!   }
+//! }
+//! ```
+
+#![allow(clippy::module_name_repetitions)]
+
+use crate::trust_atom_json::{TrustAtomJson, TrustAtomJsonError, TRUST_ATOM_JSON_VERSION};
+use crate::uri::{agent_uri, dht_location_bytes};
+use crate::TrustAtom;
+use hdk::prelude::*;
+use sha2::{Digest, Sha256};
+use std::collections::BTreeMap;
+use std::fmt;
+
+pub const CREDENTIALS_CONTEXT: &str = "https://www.w3.org/ns/credentials/v2";
+pub const TRUST_ATOM_CREDENTIAL_TYPE: &str = "TrustAtomCredential";
+pub const PROOF_TYPE: &str = "DataIntegrityProof";
+pub const CRYPTOSUITE: &str = "eddsa-jcs-2022";
+
+const DID_KEY_PREFIX: &str = "did:key:";
+/// Multicodec prefix of an Ed25519 public key
+const ED25519_PUB_MULTICODEC: [u8; 2] = [0xed, 0x01];
+/// Multibase prefix of base58btc
+const BASE58BTC_MULTIBASE: char = 'z';
+const BASE58_ALPHABET: &[u8; 58] = b"123456789ABCDEFGHJKLMNPQRSTUVWXYZabcdefghijkmnopqrstuvwxyz";
+
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq)]
+pub struct TrustAtomCredential {
+  #[serde(rename = "@context")]
+  pub context: Vec<String>,
+  #[serde(rename = "type")]
+  pub types: Vec<String>,
+  pub issuer: String,
+  #[serde(rename = "credentialSubject")]
+  pub credential_subject: CredentialSubject,
+  #[serde(default, skip_serializing_if = "Option::is_none")]
+  pub proof: Option<DataIntegrityProof>,
+}
+
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq)]
+pub struct CredentialSubject {
+  /// URI of the trust atom target
+  pub id: String,
+  #[serde(default, skip_serializing_if = "Option::is_none")]
+  pub content: Option<String>,
+  #[serde(default, skip_serializing_if = "Option::is_none")]
+  pub display_content: Option<String>,
+  #[serde(default, skip_serializing_if = "Option::is_none")]
+  pub value: Option<f64>,
+  #[serde(default, skip_serializing_if = "Option::is_none")]
+  pub confidence: Option<f64>,
+  #[serde(default, skip_serializing_if = "BTreeMap::is_empty")]
+  pub extra: BTreeMap<String, serde_json::Value>,
+}
+
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq, Eq)]
+pub struct DataIntegrityProof {
+  #[serde(rename = "type")]
+  pub proof_type: String,
+  pub cryptosuite: String,
+  #[serde(rename = "verificationMethod")]
+  pub verification_method: String,
+  #[serde(rename = "proofPurpose")]
+  pub proof_purpose: String,
+  /// Multibase (base58btc) Ed25519 signature; absent while signing
+  #[serde(
+    rename = "proofValue",
+    default,
+    skip_serializing_if = "Option::is_none"
+  )]
+  pub proof_value: Option<String>,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum CredentialError {
+  Json(String),
+  NotATrustAtomCredential,
+  UnsupportedProof(String),
+  MissingProof,
+  InvalidDidKey(String),
+  InvalidProofValue(String),
+  TrustAtom(TrustAtomJsonError),
+}
+
+impl fmt::Display for CredentialError {
+  fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+    match self {
+      Self::Json(error) => write!(f, "Invalid credential JSON: {error}"),
+      Self::NotATrustAtomCredential => write!(
+        f,
+        "Credential is not of type `{TRUST_ATOM_CREDENTIAL_TYPE}`"
+      ),
+      Self::UnsupportedProof(proof) => write!(
+        f,
+        "Unsupported proof: `{proof}` (supported: `{PROOF_TYPE}` with `{CRYPTOSUITE}`)"
+      ),
+      Self::MissingProof => write!(f, "Credential has no proof"),
+      Self::InvalidDidKey(did) => write!(f, "Not an Ed25519 did:key: `{did}`"),
+      Self::InvalidProofValue(proof_value) => write!(f, "Invalid proof value: `{proof_value}`"),
+      Self::TrustAtom(error) => write!(f, "{error}"),
+    }
+  }
+}
+
+impl std::error::Error for CredentialError {}
+
+impl From<TrustAtomJsonError> for CredentialError {
+  fn from(error: TrustAtomJsonError) -> Self {
+    Self::TrustAtom(error)
+  }
+}
+
+impl TrustAtomCredential {
+  /// Credential for a trust atom, with proof options but no proof value yet (see `signing_input`)
+  #[must_use]
+  pub fn unsigned(trust_atom: &TrustAtom) -> Self {
+    let json = TrustAtomJson::from(trust_atom);
+    let issuer = did_key(&AgentPubKey::from_raw_36(
+      trust_atom.source_hash.get_raw_36().to_vec(),
+    ));
+    Self {
+      context: vec![CREDENTIALS_CONTEXT.to_string()],
+      types: vec![
+        "VerifiableCredential".to_string(),
+        TRUST_ATOM_CREDENTIAL_TYPE.to_string(),
+      ],
+      credential_subject: CredentialSubject {
+        id: json.target,
+        content: json.content,
+        display_content: json.display_content,
+        value: json.value,
+        confidence: json.confidence,
+        extra: json.extra,
+      },
+      proof: Some(DataIntegrityProof {
+        proof_type: PROOF_TYPE.to_string(),
+        cryptosuite: CRYPTOSUITE.to_string(),
+        verification_method: verification_method(&issuer),
+        proof_purpose: "assertionMethod".to_string(),
+        proof_value: None,
+      }),
+      issuer,
+    }
+  }
+
+  pub fn to_json_string(&self) -> Result<String, CredentialError> {
+    serde_json::to_string(self).map_err(|error| CredentialError::Json(error.to_string()))
+  }
+
+  pub fn from_json_str(json: &str) -> Result<Self, CredentialError> {
+    let credential: Self =
+      serde_json::from_str(json).map_err(|error| CredentialError::Json(error.to_string()))?;
+    if credential
+      .types
+      .iter()
+      .any(|credential_type| credential_type == TRUST_ATOM_CREDENTIAL_TYPE)
+    {
+      Ok(credential)
+    } else {
+      Err(CredentialError::NotATrustAtomCredential)
+    }
+  }
+
+  /// The agent whose key signs the proof
+  pub fn issuer_agent(&self) -> Result<AgentPubKey, CredentialError> {
+    agent_from_did_key(&self.issuer)
+  }
+
+  /// The bytes signed by the proof, per `eddsa-jcs-2022`
+  pub fn signing_input(&self) -> Result<Vec<u8>, CredentialError> {
+    let proof = self.proof.as_ref().ok_or(CredentialError::MissingProof)?;
+    if proof.proof_type != PROOF_TYPE || proof.cryptosuite != CRYPTOSUITE {
+      return Err(CredentialError::UnsupportedProof(format!(
+        "{} / {}",
+        proof.proof_type, proof.cryptosuite
+      )));
+    }
+
+    let mut proof_options = to_json_value(&DataIntegrityProof {
+      proof_value: None,
+      ..proof.clone()
+    })?;
+    if let serde_json::Value::Object(proof_options) = &mut proof_options {
+      proof_options.insert("@context".to_string(), to_json_value(&self.context)?);
+    }
+    let document = to_json_value(&Self {
+      proof: None,
+      ..self.clone()
+    })?;
+
+    let mut signing_input = Sha256::digest(canonical_json(&proof_options).as_bytes()).to_vec();
+    signing_input.extend(Sha256::digest(canonical_json(&document).as_bytes()));
+    Ok(signing_input)
+  }
+
+  #[must_use]
+  pub fn with_signature(mut self, signature: &[u8]) -> Self {
+    if let Some(proof) = self.proof.as_mut() {
+      proof.proof_value = Some(format!("{BASE58BTC_MULTIBASE}{}", base58_encode(signature)));
+    }
+    self
+  }
+
+  pub fn signature(&self) -> Result<Vec<u8>, CredentialError> {
+    let proof_value = self
+      .proof
+      .as_ref()
+      .and_then(|proof| proof.proof_value.clone())
+      .ok_or(CredentialError::MissingProof)?;
+    proof_value
+      .strip_prefix(BASE58BTC_MULTIBASE)
+      .and_then(base58_decode)
+      .filter(|signature| signature.len() == 64)
+      .ok_or(CredentialError::InvalidProofValue(proof_value))
+  }
+
+  /// The trust atom this credential describes; does not check the proof
+  pub fn to_trust_atom(&self) -> Result<TrustAtom, CredentialError> {
+    let subject = &self.credential_subject;
+    let json = TrustAtomJson {
+      version: TRUST_ATOM_JSON_VERSION.to_string(),
+      source: agent_uri(&AnyLinkableHash::from(self.issuer_agent()?)),
+      target: subject.id.clone(),
+      content: subject.content.clone(),
+      display_content: subject.display_content.clone(),
+      value: subject.value,
+      confidence: subject.confidence,
+      extra: subject.extra.clone(),
+    };
+    Ok(TrustAtom::try_from(&json)?)
+  }
+}
+
+/// `did:key` identifier of an agent's Ed25519 public key
+#[must_use]
+pub fn did_key(agent: &AgentPubKey) -> String {
+  let mut multicodec = ED25519_PUB_MULTICODEC.to_vec();
+  multicodec.extend_from_slice(agent.get_raw_32());
+  format!(
+    "{DID_KEY_PREFIX}{BASE58BTC_MULTIBASE}{}",
+    base58_encode(&multicodec)
+  )
+}
+
+pub fn agent_from_did_key(did: &str) -> Result<AgentPubKey, CredentialError> {
+  let invalid = || CredentialError::InvalidDidKey(did.to_string());
+  let multicodec = did
+    .strip_prefix(DID_KEY_PREFIX)
+    .and_then(|multibase| multibase.strip_prefix(BASE58BTC_MULTIBASE))
+    .and_then(base58_decode)
+    .ok_or_else(invalid)?;
+  match multicodec.strip_prefix(&ED25519_PUB_MULTICODEC) {
+    Some(public_key) if public_key.len() == 32 => {
+      let mut raw_36 = public_key.to_vec();
+      raw_36.extend(dht_location_bytes(public_key));
+      Ok(AgentPubKey::from_raw_36(raw_36))
+    }
+    _ => Err(invalid()),
+  }
+}
+
+fn verification_method(did: &str) -> String {
+  let key = did.strip_prefix(DID_KEY_PREFIX).unwrap_or(did);
+  format!("{did}#{key}")
+}
+
+fn to_json_value<T: Serialize>(value: &T) -> Result<serde_json::Value, CredentialError> {
+  serde_json::to_value(value).map_err(|error| CredentialError::Json(error.to_string()))
+}
+
+/// JSON Canonicalization Scheme (RFC 8785): object keys sorted, no whitespace
+fn canonical_json(value: &serde_json::Value) -> String {
+  match value {
+    serde_json::Value::Object(map) => {
+      let mut entries: Vec<(&String, &serde_json::Value)> = map.iter().collect();
+      entries.sort_by(|(a, _), (b, _)| a.encode_utf16().cmp(b.encode_utf16()));
+      let members: Vec<String> = entries
+        .into_iter()
+        .map(|(key, value)| {
+          format!(
+            "{}:{}",
+            serde_json::Value::String(key.clone()),
+            canonical_json(value)
+          )
+        })
+        .collect();
+      format!("{{{}}}", members.join(","))
+    }
+    serde_json::Value::Array(values) => {
+      let values: Vec<String> = values.iter().map(canonical_json).collect();
+      format!("[{}]", values.join(","))
+    }
+    value => value.to_string(),
+  }
+}
+
+fn base58_encode(bytes: &[u8]) -> String {
+  let mut digits: Vec<u8> = vec![];
+  for byte in bytes {
+    let mut carry = u32::from(*byte);
+    for digit in &mut digits {
+      carry += u32::from(*digit) << 8;
+      *digit = u8::try_from(carry % 58).unwrap_or_default();
+      carry /= 58;
+    }
+    while carry > 0 {
+      digits.push(u8::try_from(carry % 58).unwrap_or_default());
+      carry /= 58;
+    }
+  }
+  let leading_zeros = bytes.iter().take_while(|byte| **byte == 0).count();
+  std::iter::repeat(b'1')
+    .take(leading_zeros)
+    .chain(
+      digits
+        .iter()
+        .rev()
+        .map(|digit| BASE58_ALPHABET[usize::from(*digit)]),
+    )
+    .map(char::from)
+    .collect()
+}
+
+fn base58_decode(text: &str) -> Option<Vec<u8>> {
+  let mut bytes: Vec<u8> = vec![];
+  for character in text.bytes() {
+    let mut carry = u32::try_from(
+      BASE58_ALPHABET
+        .iter()
+        .position(|alphabet_character| *alphabet_character == character)?,
+    )
+    .ok()?;
+    for byte in &mut bytes {
+      carry += u32::from(*byte) * 58;
+      *byte = u8::try_from(carry & 0xff).ok()?;
+      carry >>= 8;
+    }
+    while carry > 0 {
+      bytes.push(u8::try_from(carry & 0xff).ok()?);
+      carry >>= 8;
+    }
+  }
+  let leading_zeros = text
+    .bytes()
+    .take_while(|character| *character == b'1')
+    .count();
+  bytes.extend(std::iter::repeat(0).take(leading_zeros));
+  bytes.reverse();
+  Some(bytes)
+}
+
+#[cfg(test)]
+#[allow(clippy::unwrap_used)]
+#[allow(non_snake_case)]
+mod tests {
+
+  use super::*;
+
+  fn agent() -> AgentPubKey {
+    let mut raw_36 = vec![1; 32];
+    raw_36.extend(dht_location_bytes(&[1; 32]));
+    AgentPubKey::from_raw_36(raw_36)
+  }
+
+  fn trust_atom() -> TrustAtom {
+    TrustAtom {
+      source_hash: AnyLinkableHash::from(agent()),
+      target_hash: AnyLinkableHash::from(EntryHash::from_raw_36(vec![2; 36])),
+      content: Some("sushi".to_string()),
+      display_content: None,
+      confidence: None,
+      value: Some(".800000000".to_string()),
+      extra: Some(BTreeMap::from([(
+        "details".to_string(),
+        "Excellent specials".to_string(),
+      )])),
+    }
+  }
+
+  #[test]
+  fn test_base58() {
+    assert_eq!(base58_encode(b"hello world"), "StV1DL6CwTryKyV");
+    assert_eq!(base58_encode(&[0, 0, 1]), "112");
+    assert_eq!(base58_decode("StV1DL6CwTryKyV").unwrap(), b"hello world");
+    assert_eq!(base58_decode("112").unwrap(), vec![0, 0, 1]);
+    assert_eq!(base58_decode("0OIl"), None);
+  }
+
+  #[test]
+  fn test_did_key__round_trip() {
+    let did = did_key(&agent());
+    assert!(did.starts_with("did:key:z6Mk"));
+    assert_eq!(agent_from_did_key(&did).unwrap(), agent());
+    assert!(matches!(
+      agent_from_did_key("did:key:zQ3s"),
+      Err(CredentialError::InvalidDidKey(_))
+    ));
+  }
+
+  #[test]
+  fn test_canonical_json() {
+    let value: serde_json::Value =
+      serde_json::from_str(r#"{ "b": [1, "x", { "d": null, "c": true }], "a": 0.8 }"#).unwrap();
+    assert_eq!(
+      canonical_json(&value),
+      r#"{"a":0.8,"b":[1,"x",{"c":true,"d":null}]}"#
+    );
+  }
+
+  #[test]
+  fn test_credential__round_trip() {
+    let credential = TrustAtomCredential::unsigned(&trust_atom()).with_signature(&[7; 64]);
+    let json = credential.to_json_string().unwrap();
+    let credential = TrustAtomCredential::from_json_str(&json).unwrap();
+
+    assert_eq!(credential.issuer, did_key(&agent()));
+    assert_eq!(credential.signature().unwrap(), vec![7; 64]);
+    assert_eq!(credential.to_trust_atom().unwrap(), trust_atom());
+  }
+
+  #[test]
+  fn test_signing_input__ignores_proof_value() {
+    let unsigned = TrustAtomCredential::unsigned(&trust_atom());
+    let signed = unsigned.clone().with_signature(&[7; 64]);
+    assert_eq!(
+      unsigned.signing_input().unwrap(),
+      signed.signing_input().unwrap()
+    );
+    assert_eq!(unsigned.signing_input().unwrap().len(), 64);
+
+    let mut tampered = signed;
+    tampered.credential_subject.value = Some(-0.8);
+    assert_ne!(
+      unsigned.signing_input().unwrap(),
+      tampered.signing_input().unwrap()
+    );
+  }
+
+  #[test]
+  fn test_credential__errors() {
+    let mut credential = TrustAtomCredential::unsigned(&trust_atom());
+    assert_eq!(credential.signature(), Err(CredentialError::MissingProof));
+
+    credential.types = vec!["VerifiableCredential".to_string()];
+    let json = credential.to_json_string().unwrap();
+    assert_eq!(
+      TrustAtomCredential::from_json_str(&json),
+      Err(CredentialError::NotATrustAtomCredential)
+    );
+  }
+}
@@ -0,0 +1,230 @@
+//! Serialization of a crawled TrustGraph as Graphviz DOT, GraphML or JSON Graph Format
+
+use hdk::prelude::*;
+
+#[derive(Serialize, Deserialize, SerializedBytes, Debug, Clone, PartialEq, Eq)]
+pub struct TrustGraph {
+  pub nodes: Vec<AnyLinkableHash>,
+  pub edges: Vec<TrustGraphEdge>,
+}
+
+/// One trust atom, as a directed edge from its source to its target
+#[derive(Serialize, Deserialize, SerializedBytes, Debug, Clone, PartialEq, Eq)]
+pub struct TrustGraphEdge {
+  pub source: AnyLinkableHash,
+  pub target: AnyLinkableHash,
+  pub content: Option<String>,
+  pub value: Option<String>,
+  pub timestamp: Timestamp,
+}
+
+#[derive(Serialize, Deserialize, Debug, Clone, Copy, PartialEq, Eq)]
+pub enum GraphFormat {
+  Dot,
+  GraphMl,
+  JsonGraph,
+}
+
+impl TrustGraph {
+  /// Builds a graph from edges; nodes are all sources and targets, in sorted order
+  #[must_use]
+  pub fn from_edges(edges: Vec<TrustGraphEdge>) -> Self {
+    let mut nodes: Vec<AnyLinkableHash> = edges
+      .iter()
+      .flat_map(|edge| [edge.source.clone(), edge.target.clone()])
+      .collect();
+    nodes.sort_by_key(ToString::to_string);
+    nodes.dedup();
+    Self { nodes, edges }
+  }
+}
+
+#[must_use]
+pub fn export(graph: &TrustGraph, format: GraphFormat) -> String {
+  match format {
+    GraphFormat::Dot => to_dot(graph),
+    GraphFormat::GraphMl => to_graphml(graph),
+    GraphFormat::JsonGraph => to_json_graph(graph),
+  }
+}
+
+#[must_use]
+pub fn to_dot(graph: &TrustGraph) -> String {
+  let mut dot = String::from("digraph trustgraph {\n");
+  for node in &graph.nodes {
+    dot += &format!("  {};\n", dot_quote(&node.to_string()));
+  }
+  for edge in &graph.edges {
+    dot += &format!(
+      "  {} -> {} [label={}, content={}, value={}, timestamp={}];\n",
+      dot_quote(&edge.source.to_string()),
+      dot_quote(&edge.target.to_string()),
+      dot_quote(&edge_label(edge)),
+      dot_quote(edge.content.as_deref().unwrap_or_default()),
+      dot_quote(edge.value.as_deref().unwrap_or_default()),
+      edge.timestamp.as_micros()
+    );
+  }
+  dot += "}\n";
+  dot
+}
+
+#[must_use]
+pub fn to_graphml(graph: &TrustGraph) -> String {
+  let mut graphml = String::from(concat!(
+    "<?xml version=\"1.0\" encoding=\"UTF-8\"?>\n",
+    "<graphml xmlns=\"http://graphml.graphdrawing.org/xmlns\">\n",
+    "  <key id=\"content\" for=\"edge\" attr.name=\"content\" attr.type=\"string\"/>\n",
+    "  <key id=\"value\" for=\"edge\" attr.name=\"value\" attr.type=\"string\"/>\n",
+    "  <key id=\"timestamp\" for=\"edge\" attr.name=\"timestamp\" attr.type=\"long\"/>\n",
+    "  <graph id=\"trustgraph\" edgedefault=\"directed\">\n",
+  ));
+  for node in &graph.nodes {
+    graphml += &format!("    <node id=\"{}\"/>\n", xml_escape(&node.to_string()));
+  }
+  for edge in &graph.edges {
+    graphml += &format!(
+      "    <edge source=\"{}\" target=\"{}\">\n",
+      xml_escape(&edge.source.to_string()),
+      xml_escape(&edge.target.to_string())
+    );
+    if let Some(content) = &edge.content {
+      graphml += &format!(
+        "      <data key=\"content\">{}</data>\n",
+        xml_escape(content)
+      );
+    }
+    if let Some(value) = &edge.value {
+      graphml += &format!("      <data key=\"value\">{}</data>\n", xml_escape(value));
+    }
+    graphml += &format!(
+      "      <data key=\"timestamp\">{}</data>\n",
+      edge.timestamp.as_micros()
+    );
+    graphml += "    </edge>\n";
+  }
+  graphml += "  </graph>\n</graphml>\n";
+  graphml
+}
+
+/// JSON Graph Format, see <https://jsongraphformat.info>
+#[must_use]
+pub fn to_json_graph(graph: &TrustGraph) -> String {
+  let nodes: serde_json::Map<String, serde_json::Value> = graph
+    .nodes
+    .iter()
+    .map(|node| (node.to_string(), serde_json::json!({})))
+    .collect();
+  let edges: Vec<serde_json::Value> = graph
+    .edges
+    .iter()
+    .map(|edge| {
+      serde_json::json!({
+        "source": edge.source.to_string(),
+        "target": edge.target.to_string(),
+        "relation": edge.content,
+        "metadata": {
+          "value": edge.value,
+          "timestamp": edge.timestamp.as_micros(),
+        },
+      })
+    })
+    .collect();
+
+  serde_json::json!({
+    "graph": {
+      "directed": true,
+      "type": "trustgraph",
+      "nodes": nodes,
+      "edges": edges,
+    }
+  })
+  .to_string()
+}
+
+fn edge_label(edge: &TrustGraphEdge) -> String {
+  match (&edge.content, &edge.value) {
+    (Some(content), Some(value)) => format!("{content} {value}"),
+    (Some(content), None) => content.clone(),
+    (None, Some(value)) => value.clone(),
+    (None, None) => String::new(),
+  }
+}
+
+fn dot_quote(text: &str) -> String {
+  format!("\"{}\"", text.replace('\\', "\\\\").replace('"', "\\\""))
+}
+
+fn xml_escape(text: &str) -> String {
+  text
+    .replace('&', "&amp;")
+    .replace('<', "&lt;")
+    .replace('>', "&gt;")
+    .replace('"', "&quot;")
+    .replace('\'', "&apos;")
+}
+
+#[cfg(test)]
+#[allow(clippy::unwrap_used)]
+#[allow(non_snake_case)]
+mod tests {
+
+  use super::*;
+
+  fn fake_hash(byte: u8) -> AnyLinkableHash {
+    AnyLinkableHash::from(EntryHash::from_raw_36(vec![byte; 36]))
+  }
+
+  fn graph() -> TrustGraph {
+    TrustGraph::from_edges(vec![TrustGraphEdge {
+      source: fake_hash(1),
+      target: fake_hash(2),
+      content: Some("sushi \"omakase\" & <more>".to_string()),
+      value: Some(".800000000".to_string()),
+      timestamp: Timestamp::from_micros(1_678_312_691_145_941),
+    }])
+  }
+
+  #[test]
+  fn test_from_edges__collects_nodes() {
+    let graph = graph();
+    assert_eq!(graph.nodes.len(), 2);
+    assert!(graph.nodes.contains(&fake_hash(1)));
+    assert!(graph.nodes.contains(&fake_hash(2)));
+  }
+
+  #[test]
+  fn test_to_dot() {
+    let dot = to_dot(&graph());
+    assert!(dot.starts_with("digraph trustgraph {\n"));
+    assert!(dot.contains(&format!(
+      "\"{}\" -> \"{}\" [label=\"sushi \\\"omakase\\\" & <more> .800000000\"",
+      fake_hash(1),
+      fake_hash(2)
+    )));
+    assert!(dot.contains("timestamp=1678312691145941"));
+  }
+
+  #[test]
+  fn test_to_graphml() {
+    let graphml = to_graphml(&graph());
+    assert!(graphml.contains(&format!("<node id=\"{}\"/>", fake_hash(1))));
+    assert!(
+      graphml.contains("<data key=\"content\">sushi &quot;omakase&quot; &amp; &lt;more&gt;</data>")
+    );
+    assert!(graphml.contains("<data key=\"timestamp\">1678312691145941</data>"));
+  }
+
+  #[test]
+  fn test_to_json_graph() {
+    let json: serde_json::Value = serde_json::from_str(&to_json_graph(&graph())).unwrap();
+    let edge = &json["graph"]["edges"][0];
+    assert_eq!(edge["source"], fake_hash(1).to_string());
+    assert_eq!(edge["relation"], "sushi \"omakase\" & <more>");
+    assert_eq!(edge["metadata"]["value"], ".800000000");
+    assert_eq!(edge["metadata"]["timestamp"], 1_678_312_691_145_941_i64);
+    assert!(json["graph"]["nodes"]
+      .get(fake_hash(2).to_string())
+      .is_some());
+  }
+}
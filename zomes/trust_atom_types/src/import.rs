@@ -0,0 +1,272 @@
+//! Parsers for bulk import of trust atoms (see the `create_trust_atoms` zome function).
+//!
+//! Two layouts are supported, one trust atom per row / line, created by the importing agent:
+//!
+//! CSV, with a header row; `content`, `value` and `extra` may be left empty:
+//!
+//! ```csv
+//! target,content,value,extra
+//! hc:entry:uhCEk…,sushi,0.8,"{""details"": ""Excellent specials""}"
+//! https://trustgraph.net,,-0.5,
+//! ```
+//!
+//! JSON lines; blank lines are skipped:
+//!
+//! ```json
+//! {"target": "hc:entry:uhCEk…", "content": "sushi", "value": 0.8, "extra": {"details": "Excellent specials"}}
+//! {"target": "https://trustgraph.net", "value": "-0.5"}
+//! ```
+//!
+//! Targets are URIs, see `crate::uri`.  Values are checked when the trust atoms are created.
+
+use crate::uri::hash_from_uri;
+use crate::value::TrustAtomValue;
+use crate::TrustAtomInput;
+use hdk::prelude::*;
+use std::collections::BTreeMap;
+use std::fmt;
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ImportError {
+  /// 1-based line number in the input (for CSV, the header is line 1)
+  pub line: usize,
+  pub message: String,
+}
+
+impl fmt::Display for ImportError {
+  fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+    write!(f, "Line {}: {}", self.line, self.message)
+  }
+}
+
+impl std::error::Error for ImportError {}
+
+#[derive(Deserialize)]
+struct CsvRow {
+  target: String,
+  content: Option<String>,
+  value: Option<String>,
+  extra: Option<String>,
+}
+
+#[derive(Deserialize)]
+struct JsonLine {
+  target: String,
+  content: Option<String>,
+  value: Option<serde_json::Value>,
+  extra: Option<BTreeMap<String, serde_json::Value>>,
+}
+
+/// One result per CSV row, in order
+#[must_use]
+pub fn parse_csv(csv: &str) -> Vec<Result<TrustAtomInput, ImportError>> {
+  let mut reader = csv::ReaderBuilder::new()
+    .has_headers(true)
+    .trim(csv::Trim::All)
+    .from_reader(csv.as_bytes());
+  let headers = match reader.headers() {
+    Ok(headers) => headers.clone(),
+    Err(e) => {
+      return vec![Err(ImportError {
+        line: csv_line(e.position()),
+        message: e.to_string(),
+      })]
+    }
+  };
+
+  reader
+    .records()
+    .map(|record| {
+      let record = record.map_err(|e| ImportError {
+        line: csv_line(e.position()),
+        message: e.to_string(),
+      })?;
+      let line = csv_line(record.position());
+      let error = |message: String| ImportError { line, message };
+      let row: CsvRow = record
+        .deserialize(Some(&headers))
+        .map_err(|e| error(e.to_string()))?;
+
+      let extra = match row.extra {
+        Some(extra) => Some(parse_extra(&extra).map_err(error)?),
+        None => None,
+      };
+
+      Ok(TrustAtomInput {
+        target: hash_from_uri(&row.target).map_err(|e| error(e.to_string()))?,
+        content: row.content,
+        value: row.value.map(TrustAtomValue::Raw),
+        confidence: None,
+        extra,
+      })
+    })
+    .collect()
+}
+
+/// One result per non-blank line, in order
+#[must_use]
+pub fn parse_json_lines(json_lines: &str) -> Vec<Result<TrustAtomInput, ImportError>> {
+  json_lines
+    .lines()
+    .enumerate()
+    .filter(|(_, line)| !line.trim().is_empty())
+    .map(|(i, line)| {
+      let line_number = i + 1;
+      let error = |message: String| ImportError {
+        line: line_number,
+        message,
+      };
+      let json_line: JsonLine = serde_json::from_str(line).map_err(|e| error(e.to_string()))?;
+
+      let value = match json_line.value {
+        None | Some(serde_json::Value::Null) => None,
+        Some(serde_json::Value::String(value)) => Some(value),
+        Some(serde_json::Value::Number(value)) => Some(value.to_string()),
+        Some(value) => return Err(error(format!("Value must be a number, but got: `{value}`"))),
+      };
+
+      Ok(TrustAtomInput {
+        target: hash_from_uri(&json_line.target).map_err(|e| error(e.to_string()))?,
+        content: json_line.content,
+        value: value.map(TrustAtomValue::Raw),
+        confidence: None,
+        extra: json_line.extra.map(stringify_extra),
+      })
+    })
+    .collect()
+}
+
+/// Line a record starts on; quoted fields may span lines, so this isn't the record's index
+fn csv_line(position: Option<&csv::Position>) -> usize {
+  position.map_or(0, |position| {
+    usize::try_from(position.line()).unwrap_or(usize::MAX)
+  })
+}
+
+fn parse_extra(extra: &str) -> Result<BTreeMap<String, String>, String> {
+  serde_json::from_str::<BTreeMap<String, serde_json::Value>>(extra)
+    .map(stringify_extra)
+    .map_err(|e| format!("`extra` must be a JSON object: {e}"))
+}
+
+/// Non-string values are kept as their JSON text
+fn stringify_extra(extra: BTreeMap<String, serde_json::Value>) -> BTreeMap<String, String> {
+  extra
+    .into_iter()
+    .map(|(key, value)| match value {
+      serde_json::Value::String(value) => (key, value),
+      value => (key, value.to_string()),
+    })
+    .collect()
+}
+
+#[cfg(test)]
+#[allow(clippy::unwrap_used)]
+#[allow(non_snake_case)]
+mod tests {
+
+  use super::*;
+  use crate::uri::external_hash_for_uri;
+
+  fn entry_uri() -> (String, AnyLinkableHash) {
+    let hash = AnyLinkableHash::from(EntryHash::from_raw_36(vec![1; 36]));
+    (crate::uri::target_uri(&hash), hash)
+  }
+
+  #[test]
+  fn test_parse_csv() {
+    let (uri, hash) = entry_uri();
+    let csv = format!(
+      "target,content,value,extra\n\
+       {uri},sushi,0.8,\"{{\"\"details\"\": \"\"Excellent specials\"\", \"\"stars\"\": 5}}\"\n\
+       https://trustgraph.net,,-0.5,\n"
+    );
+    let results = parse_csv(&csv);
+    assert_eq!(results.len(), 2);
+
+    let input = results[0].clone().unwrap();
+    assert_eq!(input.target, hash);
+    assert_eq!(input.content, Some("sushi".to_string()));
+    assert_eq!(input.value, Some("0.8".into()));
+    assert_eq!(
+      input.extra,
+      Some(BTreeMap::from([
+        ("details".to_string(), "Excellent specials".to_string()),
+        ("stars".to_string(), "5".to_string()),
+      ]))
+    );
+
+    let input = results[1].clone().unwrap();
+    assert_eq!(
+      input.target,
+      AnyLinkableHash::from(external_hash_for_uri("https://trustgraph.net"))
+    );
+    assert_eq!(input.content, None);
+    assert_eq!(input.value, Some("-0.5".into()));
+    assert_eq!(input.extra, None);
+  }
+
+  #[test]
+  fn test_parse_csv__errors_per_row() {
+    let (uri, _hash) = entry_uri();
+    let csv = format!(
+      "target,content,value,extra\n\
+       Nuka Sushi,sushi,0.8,\n\
+       {uri},sushi,0.8,not json\n\
+       {uri},sushi,0.8,\n"
+    );
+    let results = parse_csv(&csv);
+    assert_eq!(results.len(), 3);
+    assert_eq!(results[0].clone().unwrap_err().line, 2);
+    assert!(results[1]
+      .clone()
+      .unwrap_err()
+      .message
+      .contains("`extra` must be a JSON object"));
+    assert!(results[2].is_ok());
+  }
+
+  #[test]
+  fn test_parse_csv__errors_after_multi_line_field() {
+    let (uri, _hash) = entry_uri();
+    let csv = format!(
+      "target,content,value,extra\n\
+       {uri},sushi,0.8,\"{{\"\"details\"\":\n\"\"Excellent specials\"\"}}\"\n\
+       Nuka Sushi,sushi,0.8,\n"
+    );
+    let results = parse_csv(&csv);
+    assert_eq!(results.len(), 2);
+    assert!(results[0].is_ok());
+    assert_eq!(results[1].clone().unwrap_err().line, 4);
+  }
+
+  #[test]
+  fn test_parse_json_lines() {
+    let (uri, hash) = entry_uri();
+    let json_lines = format!(
+      "{{\"target\": \"{uri}\", \"content\": \"sushi\", \"value\": 0.8, \"extra\": {{\"verified\": true}}}}\n\
+       \n\
+       {{\"target\": \"https://trustgraph.net\", \"value\": \"-0.5\"}}\n\
+       {{\"target\": \"{uri}\", \"value\": [1]}}\n"
+    );
+    let results = parse_json_lines(&json_lines);
+    assert_eq!(results.len(), 3);
+
+    let input = results[0].clone().unwrap();
+    assert_eq!(input.target, hash);
+    assert_eq!(input.value, Some("0.8".into()));
+    assert_eq!(
+      input.extra,
+      Some(BTreeMap::from([(
+        "verified".to_string(),
+        "true".to_string()
+      )]))
+    );
+
+    let input = results[1].clone().unwrap();
+    assert_eq!(input.content, None);
+    assert_eq!(input.value, Some("-0.5".into()));
+
+    assert_eq!(results[2].clone().unwrap_err().line, 4);
+  }
+}
@@ -13,14 +13,43 @@
 use hdk::prelude::*;
 use std::collections::BTreeMap;
 
+pub mod bundle;
+pub mod content;
+pub mod credential;
+pub mod graph_export;
+pub mod import;
+pub mod string_target;
+pub mod trust_atom_json;
+pub mod uri;
+pub mod value;
+
 #[derive(Serialize, Deserialize, SerializedBytes, Debug, Clone)]
 pub struct TrustAtomInput {
   pub target: AnyLinkableHash,
   pub content: Option<String>,
-  pub value: Option<String>,
+  /// A decimal string in the range -1..1, or a typed value such as stars; see `value`
+  pub value: Option<value::TrustAtomValue>,
+  /// How sure the rater is of `value`, a decimal string in the range 0..1; aggregates weight the
+  /// trust atom by it.  Defaults to fully sure.
+  pub confidence: Option<String>,
   pub extra: Option<BTreeMap<String, String>>,
 }
 
+/// Free-form text to rate, eg `Joe's Diner`; normalized by `string_target::normalize`
+#[derive(Serialize, Deserialize, SerializedBytes, Debug, Clone, PartialEq, Eq)]
+pub struct StringTargetInput {
+  pub text: String,
+  /// Kind of identifier the text is, eg `isbn`
+  pub namespace: Option<String>,
+}
+
+/// Outcome of one item of a `create_trust_atoms` batch: exactly one of the fields is set
+#[derive(Serialize, Deserialize, SerializedBytes, Debug, Clone, PartialEq, Eq)]
+pub struct CreateTrustAtomResult {
+  pub trust_atom: Option<TrustAtom>,
+  pub error: Option<String>,
+}
+
 #[derive(Serialize, Deserialize, SerializedBytes, Debug, Clone)]
 pub struct QueryInput {
   pub source: Option<AnyLinkableHash>,
@@ -28,6 +57,9 @@ pub struct QueryInput {
   pub content_full: Option<String>,
   pub content_starts_with: Option<String>,
   pub value_starts_with: Option<String>,
+  /// Fail on the first link which can't be decoded as a trust atom, rather than reporting it in
+  /// `QueryOutput::decode_errors`; defaults to `false`
+  pub strict: Option<bool>,
 }
 
 #[derive(Serialize, Deserialize, SerializedBytes, Debug, Clone)]
@@ -36,6 +68,31 @@ pub struct QueryMineInput {
   pub content_full: Option<String>,
   pub content_starts_with: Option<String>,
   pub value_starts_with: Option<String>,
+  /// As in `QueryInput`
+  pub strict: Option<bool>,
+}
+
+#[derive(Serialize, Deserialize, SerializedBytes, Debug, Clone, Default, PartialEq, Eq)]
+pub struct QueryOutput {
+  pub trust_atoms: Vec<TrustAtom>,
+  /// Links matching the query which are not trust atoms this zome can read, eg another app's links
+  /// or a newer tag format
+  pub decode_errors: Vec<LinkDecodeError>,
+}
+
+#[derive(Serialize, Deserialize, SerializedBytes, Debug, Clone, PartialEq, Eq)]
+pub struct LinkDecodeError {
+  pub create_link_hash: ActionHash,
+  pub error: String,
+}
+
+/// Like `QueryInput`, for trust atoms about an external resource, identified by its URI
+#[derive(Serialize, Deserialize, SerializedBytes, Debug, Clone)]
+pub struct QueryExternalTargetInput {
+  pub uri: String,
+  pub content_full: Option<String>,
+  pub content_starts_with: Option<String>,
+  pub value_starts_with: Option<String>,
 }
 
 #[derive(Serialize, Deserialize, SerializedBytes, Debug, Clone)]
@@ -52,6 +109,227 @@ pub struct TrustAtom {
   pub source_hash: AnyLinkableHash,
   pub target_hash: AnyLinkableHash,
   pub content: Option<String>,
+  /// `content` as entered, if the DNA's content normalization changed it
+  pub display_content: Option<String>,
   pub value: Option<String>,
+  /// As in `TrustAtomInput`, normalized like `value`
+  pub confidence: Option<String>,
   pub extra: Option<BTreeMap<String, String>>,
 }
+
+#[derive(Serialize, Deserialize, SerializedBytes, Debug, Clone, PartialEq, Eq)]
+pub struct TimestampedTrustAtom {
+  pub trust_atom: TrustAtom,
+  pub timestamp: Timestamp,
+}
+
+/// Contents of a `bundle::TrustAtomBundle` whose signature has been checked
+#[derive(Serialize, Deserialize, SerializedBytes, Debug, Clone, PartialEq, Eq)]
+pub struct VerifiedTrustAtomBundle {
+  pub agent: AgentPubKey,
+  pub exported_at: Timestamp,
+  pub trust_atoms: Vec<TimestampedTrustAtom>,
+}
+
+#[derive(Serialize, Deserialize, SerializedBytes, Debug, Clone)]
+pub struct MigrateTrustAtomsInput {
+  /// Most trust atoms to rewrite in this call; call again while `remaining` is above 0
+  pub batch_size: usize,
+  /// Report what would be migrated, without changing anything
+  pub dry_run: bool,
+}
+
+#[derive(Serialize, Deserialize, SerializedBytes, Debug, Clone, PartialEq, Eq)]
+pub struct MigrationReport {
+  pub dry_run: bool,
+  /// Trust atoms rewritten in this call, or that would have been in a dry run
+  pub migrated: usize,
+  /// Trust atoms left in another format after this call
+  pub remaining: usize,
+  /// Trust atoms which were already in the current format
+  pub current: usize,
+  /// My links which could not be decoded as trust atoms; these are left alone
+  pub undecodable: usize,
+  /// Forward links of trust atoms which could not be migrated, eg as their content is over the
+  /// current size limits; these are left alone, and tried again by the next call
+  pub failed: Vec<ActionHash>,
+}
+
+#[derive(Serialize, Deserialize, SerializedBytes, Debug, Clone)]
+pub struct RollupInput {
+  /// Agent to crawl from; defaults to the calling agent
+  pub seed: Option<AnyLinkableHash>,
+  pub content_full: Option<String>,
+  pub content_starts_with: Option<String>,
+  /// Defaults to `RaterWeighting::Equal`
+  pub weighting: Option<RaterWeighting>,
+}
+
+impl RollupInput {
+  /// Canonical form of the rollup parameters, used to key cached rollups
+  #[must_use]
+  pub fn cache_key(&self) -> String {
+    format!(
+      "content_full={:?};content_starts_with={:?};weighting={:?}",
+      self.content_full, self.content_starts_with, self.weighting
+    )
+  }
+}
+
+/// How much each rater counts in an aggregate, as seen from the calling agent's TrustGraph
+#[derive(Serialize, Deserialize, Debug, Clone, Copy, PartialEq, Eq)]
+pub enum RaterWeighting {
+  /// Every rater counts equally
+  Equal,
+  /// Each rater is weighted by the trust the caller's graph places in them (up to 2 hops out);
+  /// raters with no incoming trust are heavily discounted, but still count
+  IncomingTrust,
+  /// As `IncomingTrust`, but raters with no path from the caller are dropped entirely
+  RequirePath,
+}
+
+#[derive(Serialize, Deserialize, SerializedBytes, Debug, Clone)]
+pub struct SummarizeTargetInput {
+  pub target: AnyLinkableHash,
+  pub content_full: Option<String>,
+  pub content_starts_with: Option<String>,
+  /// Defaults to `RaterWeighting::Equal`
+  pub weighting: Option<RaterWeighting>,
+}
+
+#[derive(Serialize, Deserialize, SerializedBytes, Debug, Clone, PartialEq, Eq)]
+pub struct TargetSummary {
+  pub target_hash: AnyLinkableHash,
+  /// Weighted mean of the counted ratings; `None` if no rating counted
+  pub value: Option<String>,
+  pub ratings_counted: usize,
+  pub ratings_dropped: usize,
+}
+
+#[derive(Serialize, Deserialize, SerializedBytes, Debug, Clone, PartialEq, Eq)]
+pub struct RollupScore {
+  pub target_hash: AnyLinkableHash,
+  pub value: String,
+  pub raters: usize,
+}
+
+#[derive(Serialize, Deserialize, SerializedBytes, Debug, Clone, PartialEq, Eq)]
+pub struct Rollup {
+  pub seed: AnyLinkableHash,
+  pub scores: Vec<RollupScore>,
+}
+
+#[derive(Serialize, Deserialize, SerializedBytes, Debug, Clone)]
+pub struct CacheRollupInput {
+  pub rollup: RollupInput,
+  /// Store the cached rollup as a private entry on my source chain, rather than publishing it
+  pub private: bool,
+}
+
+#[derive(Serialize, Deserialize, SerializedBytes, Debug, Clone)]
+pub struct GetCachedRollupInput {
+  pub rollup: RollupInput,
+  /// Recompute (and re-cache) the rollup if the cached copy is stale
+  pub refresh_if_stale: bool,
+  /// Visibility used when a refreshed rollup is cached
+  pub private: bool,
+}
+
+#[derive(Serialize, Deserialize, SerializedBytes, Debug, Clone)]
+pub struct CachedRollupOutput {
+  pub rollup: Option<Rollup>,
+  pub computed_at: Option<Timestamp>,
+  /// True when any trust atom in the seed's first hop has been created or deleted since
+  /// the rollup was computed
+  pub stale: bool,
+}
+
+#[derive(Serialize, Deserialize, Debug, Clone, Copy, PartialEq, Eq)]
+pub enum SimilarityMetric {
+  Cosine,
+  Pearson,
+}
+
+#[derive(Serialize, Deserialize, SerializedBytes, Debug, Clone)]
+pub struct AgentSimilarityInput {
+  pub agent: AnyLinkableHash,
+  /// Defaults to the calling agent
+  pub compared_to: Option<AnyLinkableHash>,
+  pub metric: SimilarityMetric,
+}
+
+#[derive(Serialize, Deserialize, SerializedBytes, Debug, Clone)]
+pub struct SimilarAgentsInput {
+  pub metric: SimilarityMetric,
+  pub limit: usize,
+  /// Number of hops (my targets -> their raters -> their targets -> ...) to look for candidates
+  pub radius: u8,
+  /// Ignore agents with fewer ratings in common with me; defaults to 1
+  pub min_shared_ratings: Option<usize>,
+}
+
+/// Similarity of `agent`'s ratings to those of the agent compared to, over shared
+/// (target, content) pairs.  `similarity` is in -1..1, or `None` if it is undefined
+/// (eg no shared ratings).
+#[derive(Serialize, Deserialize, SerializedBytes, Debug, Clone, PartialEq)]
+pub struct AgentSimilarity {
+  pub agent: AnyLinkableHash,
+  pub similarity: Option<f64>,
+  pub shared_ratings: usize,
+}
+
+/// Whose ratings recommendations are drawn from
+#[derive(Serialize, Deserialize, Debug, Clone, Copy, PartialEq, Eq)]
+pub enum RecommendVia {
+  /// Agents I rated positively, weighted by my value for them
+  TrustedAgents,
+  /// Agents whose ratings correlate positively with mine, weighted by similarity
+  SimilarAgents,
+  Both,
+}
+
+#[derive(Serialize, Deserialize, SerializedBytes, Debug, Clone)]
+pub struct RecommendInput {
+  pub content: String,
+  pub limit: usize,
+  /// Defaults to `RecommendVia::TrustedAgents`
+  pub via: Option<RecommendVia>,
+}
+
+#[derive(Serialize, Deserialize, SerializedBytes, Debug, Clone, PartialEq, Eq)]
+pub struct Recommendation {
+  pub target_hash: AnyLinkableHash,
+  /// Weighted mean of the positive ratings of the target by the recommending agents who rated it
+  pub score: String,
+  /// The positive trust atoms this recommendation is based on
+  pub provenance: Vec<TrustAtom>,
+}
+
+#[derive(Serialize, Deserialize, SerializedBytes, Debug, Clone)]
+pub struct ExportGraphInput {
+  /// Agent to crawl from; defaults to the calling agent
+  pub seed: Option<AnyLinkableHash>,
+  /// Hops to crawl beyond the seed's own trust atoms; 0 exports just the seed's atoms
+  pub depth: u8,
+  pub format: graph_export::GraphFormat,
+}
+
+/// Trust atom settings read from the DNA properties, eg in `dna.yaml`:
+/// `properties: { tag_format: compact }`.  Other properties are ignored.
+#[derive(Serialize, Deserialize, Debug, Clone, Default)]
+#[serde(default)]
+pub struct TrustAtomProperties {
+  pub tag_format: TagFormat,
+  pub content_normalization: content::ContentNormalization,
+}
+
+/// Link tag format for new trust atoms; atoms in any format are always read
+#[derive(Serialize, Deserialize, Debug, Clone, Copy, Default, PartialEq, Eq)]
+#[serde(rename_all = "snake_case")]
+pub enum TagFormat {
+  /// NUL separated text chunks
+  #[default]
+  Text,
+  /// Value, bucket and extra hash as binary, leaving more room for content
+  Compact,
+}
@@ -0,0 +1,107 @@
+//! Canonical form of free-form text targets (eg `Joe's Diner`, or `9780261103573` in the `isbn`
+//! namespace), so that the same thing written slightly differently yields the same entry hash.
+
+#![allow(clippy::module_name_repetitions)]
+
+use crate::StringTargetInput;
+use std::fmt;
+use unicode_normalization::UnicodeNormalization;
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum StringTargetError {
+  EmptyText,
+  /// Namespaces follow URI scheme syntax: `ALPHA *( ALPHA / DIGIT / "+" / "-" / "." )`
+  InvalidNamespace(String),
+}
+
+impl fmt::Display for StringTargetError {
+  fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+    match self {
+      Self::EmptyText => write!(f, "String target text must not be empty"),
+      Self::InvalidNamespace(namespace) => write!(
+        f,
+        "String target namespace must be a letter followed by letters, digits, `+`, `-` or `.`, but got: `{namespace}`"
+      ),
+    }
+  }
+}
+
+impl std::error::Error for StringTargetError {}
+
+/// Text trimmed and in Unicode Normalization Form C; namespace trimmed, lowercased,
+/// and without a trailing `:` (so `ISBN:` and `isbn` are the same namespace)
+pub fn normalize(input: &StringTargetInput) -> Result<StringTargetInput, StringTargetError> {
+  let text: String = input.text.trim().nfc().collect();
+  if text.is_empty() {
+    return Err(StringTargetError::EmptyText);
+  }
+
+  let namespace = match &input.namespace {
+    Some(namespace) => Some(normalize_namespace(namespace)?),
+    None => None,
+  };
+
+  Ok(StringTargetInput { text, namespace })
+}
+
+fn normalize_namespace(namespace: &str) -> Result<String, StringTargetError> {
+  let normalized = namespace.trim().trim_end_matches(':').to_ascii_lowercase();
+  let valid = normalized.starts_with(|c: char| c.is_ascii_alphabetic())
+    && normalized
+      .chars()
+      .all(|c| c.is_ascii_alphanumeric() || matches!(c, '+' | '-' | '.'));
+  if valid {
+    Ok(normalized)
+  } else {
+    Err(StringTargetError::InvalidNamespace(namespace.to_string()))
+  }
+}
+
+#[cfg(test)]
+#[allow(clippy::unwrap_used)]
+#[allow(non_snake_case)]
+mod tests {
+
+  use super::*;
+
+  fn input(text: &str, namespace: Option<&str>) -> StringTargetInput {
+    StringTargetInput {
+      text: text.to_string(),
+      namespace: namespace.map(ToString::to_string),
+    }
+  }
+
+  #[test]
+  fn test_normalize__trims_and_composes() {
+    // "Café" with a combining acute accent, padded with whitespace
+    let normalized = normalize(&input("  Cafe\u{301} Nuka\n", None)).unwrap();
+    assert_eq!(normalized.text, "Caf\u{e9} Nuka");
+    assert_eq!(normalized.namespace, None);
+    assert_eq!(
+      normalized,
+      normalize(&input("Caf\u{e9} Nuka", None)).unwrap()
+    );
+  }
+
+  #[test]
+  fn test_normalize__namespace() {
+    let normalized = normalize(&input("9780261103573", Some(" ISBN: "))).unwrap();
+    assert_eq!(normalized.namespace, Some("isbn".to_string()));
+    assert_eq!(
+      normalized,
+      normalize(&input("9780261103573", Some("isbn"))).unwrap()
+    );
+  }
+
+  #[test]
+  fn test_normalize__errors() {
+    assert_eq!(
+      normalize(&input(" \t", None)),
+      Err(StringTargetError::EmptyText)
+    );
+    assert_eq!(
+      normalize(&input("Joe's Diner", Some("9 lives"))),
+      Err(StringTargetError::InvalidNamespace("9 lives".to_string()))
+    );
+  }
+}
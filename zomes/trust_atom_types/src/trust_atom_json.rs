@@ -0,0 +1,310 @@
+//! Canonical, versioned JSON serialization of a `TrustAtom`, for exchanging trust atoms with
+//! non-Holochain TrustGraph implementations (eg js-trustgraph-core):
+//!
+//! ```json
+//! {
+//!   "version": "trustgraph/trust_atom/1",
+//!   "source": "hc:agent:uhCAk…",
+//!   "target": "hc:entry:uhCEk…",
+//!   "content": "sushi",
+//!   "value": 0.8,
+//!   "confidence": 0.5,
+//!   "extra": { "details": "Excellent specials" }
+//! }
+//! ```
+
+#![allow(clippy::module_name_repetitions)]
+
+use crate::uri::{agent_uri, hash_from_uri, target_uri, UriError};
+use crate::value::normalize_value;
+use crate::TrustAtom;
+use hdk::prelude::*;
+use std::collections::BTreeMap;
+use std::fmt;
+
+pub const TRUST_ATOM_JSON_VERSION: &str = "trustgraph/trust_atom/1";
+
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq)]
+pub struct TrustAtomJson {
+  pub version: String,
+  pub source: String,
+  pub target: String,
+  #[serde(default, skip_serializing_if = "Option::is_none")]
+  pub content: Option<String>,
+  /// `content` as entered, if the DNA's content normalization changed it
+  #[serde(default, skip_serializing_if = "Option::is_none")]
+  pub display_content: Option<String>,
+  /// In the range -1..1
+  #[serde(default, skip_serializing_if = "Option::is_none")]
+  pub value: Option<f64>,
+  /// In the range 0..1
+  #[serde(default, skip_serializing_if = "Option::is_none")]
+  pub confidence: Option<f64>,
+  #[serde(default, skip_serializing_if = "BTreeMap::is_empty")]
+  pub extra: BTreeMap<String, serde_json::Value>,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum TrustAtomJsonError {
+  Json(String),
+  UnsupportedVersion(String),
+  Uri(UriError),
+  InvalidValue(String),
+  InvalidConfidence(String),
+}
+
+impl fmt::Display for TrustAtomJsonError {
+  fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+    match self {
+      Self::Json(error) => write!(f, "Invalid TrustAtom JSON: {error}"),
+      Self::UnsupportedVersion(version) => write!(
+        f,
+        "Unsupported TrustAtom JSON version: `{version}` (supported: `{TRUST_ATOM_JSON_VERSION}`)"
+      ),
+      Self::Uri(error) => write!(f, "{error}"),
+      Self::InvalidValue(value) => {
+        write!(
+          f,
+          "Value must be a number in the range -1..1, but got: `{value}`"
+        )
+      }
+      Self::InvalidConfidence(confidence) => {
+        write!(
+          f,
+          "Confidence must be a number in the range 0..1, but got: `{confidence}`"
+        )
+      }
+    }
+  }
+}
+
+impl std::error::Error for TrustAtomJsonError {}
+
+impl From<UriError> for TrustAtomJsonError {
+  fn from(error: UriError) -> Self {
+    Self::Uri(error)
+  }
+}
+
+impl From<&TrustAtom> for TrustAtomJson {
+  fn from(trust_atom: &TrustAtom) -> Self {
+    Self {
+      version: TRUST_ATOM_JSON_VERSION.to_string(),
+      source: agent_uri(&trust_atom.source_hash),
+      target: target_uri(&trust_atom.target_hash),
+      content: trust_atom
+        .content
+        .clone()
+        .filter(|content| !content.is_empty()),
+      display_content: trust_atom.display_content.clone(),
+      value: trust_atom
+        .value
+        .as_ref()
+        .and_then(|value| value.parse::<f64>().ok()),
+      confidence: trust_atom
+        .confidence
+        .as_ref()
+        .and_then(|confidence| confidence.parse::<f64>().ok()),
+      extra: trust_atom
+        .extra
+        .clone()
+        .unwrap_or_default()
+        .into_iter()
+        .map(|(key, value)| (key, serde_json::Value::String(value)))
+        .collect(),
+    }
+  }
+}
+
+impl TryFrom<&TrustAtomJson> for TrustAtom {
+  type Error = TrustAtomJsonError;
+
+  fn try_from(json: &TrustAtomJson) -> Result<Self, Self::Error> {
+    if json.version != TRUST_ATOM_JSON_VERSION {
+      return Err(TrustAtomJsonError::UnsupportedVersion(json.version.clone()));
+    }
+
+    let value = match json.value {
+      Some(value) => Some(format_value(value)?),
+      None => None,
+    };
+    let confidence = match json.confidence {
+      Some(confidence) => Some(format_confidence(confidence)?),
+      None => None,
+    };
+
+    // non-string extra values are kept as their JSON text
+    let extra: BTreeMap<String, String> = json
+      .extra
+      .iter()
+      .map(|(key, value)| match value {
+        serde_json::Value::String(value) => (key.clone(), value.clone()),
+        value => (key.clone(), value.to_string()),
+      })
+      .collect();
+
+    Ok(Self {
+      source_hash: hash_from_uri(&json.source)?,
+      target_hash: hash_from_uri(&json.target)?,
+      content: json.content.clone(),
+      display_content: json.display_content.clone(),
+      confidence,
+      value,
+      extra: if extra.is_empty() { None } else { Some(extra) },
+    })
+  }
+}
+
+pub fn to_json_string(trust_atom: &TrustAtom) -> Result<String, TrustAtomJsonError> {
+  serde_json::to_string(&TrustAtomJson::from(trust_atom))
+    .map_err(|error| TrustAtomJsonError::Json(error.to_string()))
+}
+
+pub fn from_json_str(json: &str) -> Result<TrustAtom, TrustAtomJsonError> {
+  let trust_atom_json: TrustAtomJson =
+    serde_json::from_str(json).map_err(|error| TrustAtomJsonError::Json(error.to_string()))?;
+  TrustAtom::try_from(&trust_atom_json)
+}
+
+/// Renders a value in its canonical form, as in link tags (eg `-.800000000`)
+fn format_value(value: f64) -> Result<String, TrustAtomJsonError> {
+  normalize_value(&value.to_string())
+    .map_err(|_| TrustAtomJsonError::InvalidValue(value.to_string()))
+}
+
+/// As `format_value`, for the range 0..1
+fn format_confidence(confidence: f64) -> Result<String, TrustAtomJsonError> {
+  match format_value(confidence) {
+    Ok(formatted) if confidence >= 0.0 => Ok(formatted),
+    _ => Err(TrustAtomJsonError::InvalidConfidence(
+      confidence.to_string(),
+    )),
+  }
+}
+
+#[cfg(test)]
+#[allow(clippy::unwrap_used)]
+#[allow(non_snake_case)]
+mod tests {
+
+  use super::*;
+
+  fn trust_atom() -> TrustAtom {
+    TrustAtom {
+      source_hash: AnyLinkableHash::from(AgentPubKey::from_raw_36(vec![1; 36])),
+      target_hash: AnyLinkableHash::from(EntryHash::from_raw_36(vec![2; 36])),
+      content: Some("sushi".to_string()),
+      display_content: Some("Sushi".to_string()),
+      confidence: Some(".500000000".to_string()),
+      value: Some("-.800000000".to_string()),
+      extra: Some(BTreeMap::from([(
+        "details".to_string(),
+        "Excellent specials".to_string(),
+      )])),
+    }
+  }
+
+  #[test]
+  fn test_json__round_trip() {
+    let json = to_json_string(&trust_atom()).unwrap();
+    assert_eq!(from_json_str(&json).unwrap(), trust_atom());
+  }
+
+  #[test]
+  fn test_json__format() {
+    let json: serde_json::Value =
+      serde_json::from_str(&to_json_string(&trust_atom()).unwrap()).unwrap();
+    assert_eq!(json["version"], TRUST_ATOM_JSON_VERSION);
+    assert!(json["source"]
+      .as_str()
+      .unwrap()
+      .starts_with("hc:agent:uhCAk"));
+    assert!(json["target"]
+      .as_str()
+      .unwrap()
+      .starts_with("hc:entry:uhCEk"));
+    assert_eq!(json["value"], -0.8);
+    assert_eq!(json["confidence"], 0.5);
+    assert_eq!(json["extra"]["details"], "Excellent specials");
+  }
+
+  #[test]
+  fn test_json__foreign_atom() {
+    let json = format!(
+      r#"{{
+        "version": "{TRUST_ATOM_JSON_VERSION}",
+        "source": "{}",
+        "target": "https://trustgraph.net",
+        "value": 1,
+        "extra": {{ "stars": 5, "verified": true }}
+      }}"#,
+      agent_uri(&trust_atom().source_hash)
+    );
+    let trust_atom = from_json_str(&json).unwrap();
+
+    assert_eq!(
+      trust_atom.target_hash,
+      AnyLinkableHash::from(crate::uri::external_hash_for_uri("https://trustgraph.net"))
+    );
+    assert_eq!(trust_atom.content, None);
+    assert_eq!(trust_atom.value, Some(".999999999".to_string()));
+    assert_eq!(trust_atom.confidence, None);
+    assert_eq!(
+      trust_atom.extra,
+      Some(BTreeMap::from([
+        ("stars".to_string(), "5".to_string()),
+        ("verified".to_string(), "true".to_string()),
+      ]))
+    );
+  }
+
+  #[test]
+  fn test_json__canonical_values() {
+    let values_and_expected = [
+      (1.0, ".999999999"),
+      (-1.0, "-.999999999"),
+      (0.0, "0"),
+      (-0.8, "-.800000000"),
+    ];
+    for (value, expected) in values_and_expected {
+      let mut json = TrustAtomJson::from(&trust_atom());
+      json.value = Some(value);
+      assert_eq!(
+        TrustAtom::try_from(&json).unwrap().value,
+        Some(expected.to_string()),
+        "{value}"
+      );
+    }
+  }
+
+  #[test]
+  fn test_json__errors() {
+    let mut json = TrustAtomJson::from(&trust_atom());
+    json.version = "trustgraph/trust_atom/99".to_string();
+    assert!(matches!(
+      TrustAtom::try_from(&json),
+      Err(TrustAtomJsonError::UnsupportedVersion(_))
+    ));
+
+    let mut json = TrustAtomJson::from(&trust_atom());
+    json.value = Some(1.5);
+    assert!(matches!(
+      TrustAtom::try_from(&json),
+      Err(TrustAtomJsonError::InvalidValue(_))
+    ));
+
+    for confidence in [-0.5, 1.5] {
+      let mut json = TrustAtomJson::from(&trust_atom());
+      json.confidence = Some(confidence);
+      assert!(matches!(
+        TrustAtom::try_from(&json),
+        Err(TrustAtomJsonError::InvalidConfidence(_))
+      ));
+    }
+
+    assert!(matches!(
+      from_json_str("{}"),
+      Err(TrustAtomJsonError::Json(_))
+    ));
+  }
+}
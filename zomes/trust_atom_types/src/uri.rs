@@ -0,0 +1,171 @@
+//! URIs for trust atom sources and targets, as used by other TrustGraph implementations:
+//! `hc:agent:uhCAk…`, `hc:entry:uhCEk…`, `hc:action:uhCkk…`, `hc:external:uhC8k…`,
+//! or any other absolute URI (`https://…`, `ipfs://…`), which maps to an `ExternalHash`.
+
+#![allow(clippy::module_name_repetitions)]
+
+use hdk::prelude::*;
+use std::fmt;
+
+pub const AGENT_URI_PREFIX: &str = "hc:agent:";
+pub const ENTRY_URI_PREFIX: &str = "hc:entry:";
+pub const ACTION_URI_PREFIX: &str = "hc:action:";
+pub const EXTERNAL_URI_PREFIX: &str = "hc:external:";
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum UriError {
+  /// Not of the form `scheme:…`
+  NotAUri(String),
+  /// A `hc:` URI whose hash could not be decoded
+  InvalidHash(String),
+}
+
+impl fmt::Display for UriError {
+  fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+    match self {
+      Self::NotAUri(uri) => write!(f, "Not a URI: `{uri}`"),
+      Self::InvalidHash(uri) => write!(f, "URI does not contain a valid hash: `{uri}`"),
+    }
+  }
+}
+
+impl std::error::Error for UriError {}
+
+/// URI of an agent, eg a trust atom source
+#[must_use]
+pub fn agent_uri(agent: &AnyLinkableHash) -> String {
+  let agent = AgentPubKey::from_raw_36(agent.get_raw_36().to_vec());
+  format!("{AGENT_URI_PREFIX}{agent}")
+}
+
+/// URI of a trust atom target.  Agents cannot be told apart from entries once converted
+/// to `AnyLinkableHash`, so agent targets are rendered as `hc:entry:` URIs.
+#[must_use]
+pub fn target_uri(target: &AnyLinkableHash) -> String {
+  match target.clone().into_primitive() {
+    AnyLinkablePrimitive::Entry(hash) => format!("{ENTRY_URI_PREFIX}{hash}"),
+    AnyLinkablePrimitive::Action(hash) => format!("{ACTION_URI_PREFIX}{hash}"),
+    AnyLinkablePrimitive::External(hash) => format!("{EXTERNAL_URI_PREFIX}{hash}"),
+  }
+}
+
+/// Inverse of `agent_uri` / `target_uri`; any other absolute URI maps to its external hash
+pub fn hash_from_uri(uri: &str) -> Result<AnyLinkableHash, UriError> {
+  let invalid_hash = |_| UriError::InvalidHash(uri.to_string());
+
+  if let Some(b64) = uri.strip_prefix(AGENT_URI_PREFIX) {
+    let agent: AgentPubKey = AgentPubKeyB64::from_b64_str(b64)
+      .map_err(invalid_hash)?
+      .into();
+    Ok(AnyLinkableHash::from(agent))
+  } else if let Some(b64) = uri.strip_prefix(ENTRY_URI_PREFIX) {
+    let entry_hash: EntryHash = EntryHashB64::from_b64_str(b64)
+      .map_err(invalid_hash)?
+      .into();
+    Ok(AnyLinkableHash::from(entry_hash))
+  } else if let Some(b64) = uri.strip_prefix(ACTION_URI_PREFIX) {
+    let action_hash: ActionHash = ActionHashB64::from_b64_str(b64)
+      .map_err(invalid_hash)?
+      .into();
+    Ok(AnyLinkableHash::from(action_hash))
+  } else if let Some(b64) = uri.strip_prefix(EXTERNAL_URI_PREFIX) {
+    let external_hash: ExternalHash = ExternalHashB64::from_b64_str(b64)
+      .map_err(invalid_hash)?
+      .into();
+    Ok(AnyLinkableHash::from(external_hash))
+  } else if is_absolute_uri(uri) {
+    Ok(AnyLinkableHash::from(external_hash_for_uri(uri)))
+  } else {
+    Err(UriError::NotAUri(uri.to_string()))
+  }
+}
+
+/// Deterministic `ExternalHash` for any URI (or IPFS CID, etc):
+/// the 32 byte blake2b hash of the URI, plus DHT location bytes derived as Holochain does.
+#[must_use]
+pub fn external_hash_for_uri(uri: &str) -> ExternalHash {
+  let hash = blake2b_simd::Params::new()
+    .hash_length(32)
+    .hash(uri.as_bytes());
+  let mut raw_36 = hash.as_bytes().to_vec();
+  raw_36.extend(dht_location_bytes(hash.as_bytes()));
+  ExternalHash::from_raw_36(raw_36)
+}
+
+/// The 4 DHT location bytes Holochain appends to a 32 byte hash or public key
+pub(crate) fn dht_location_bytes(data: &[u8]) -> [u8; 4] {
+  let hash = blake2b_simd::Params::new().hash_length(16).hash(data);
+  let hash = hash.as_bytes();
+  let mut location = [hash[0], hash[1], hash[2], hash[3]];
+  for chunk in hash[4..16].chunks(4) {
+    for (location_byte, hash_byte) in location.iter_mut().zip(chunk) {
+      *location_byte ^= hash_byte;
+    }
+  }
+  location
+}
+
+/// `scheme:rest`, where scheme is `ALPHA *( ALPHA / DIGIT / "+" / "-" / "." )` (RFC 3986)
+#[must_use]
+pub fn is_absolute_uri(uri: &str) -> bool {
+  match uri.split_once(':') {
+    Some((scheme, rest)) => {
+      !rest.is_empty()
+        && scheme.starts_with(|c: char| c.is_ascii_alphabetic())
+        && scheme
+          .chars()
+          .all(|c| c.is_ascii_alphanumeric() || matches!(c, '+' | '-' | '.'))
+    }
+    None => false,
+  }
+}
+
+#[cfg(test)]
+#[allow(clippy::unwrap_used)]
+#[allow(non_snake_case)]
+mod tests {
+
+  use super::*;
+
+  #[test]
+  fn test_agent_uri__round_trip() {
+    let agent = AnyLinkableHash::from(AgentPubKey::from_raw_36(vec![7; 36]));
+    let uri = agent_uri(&agent);
+    assert!(uri.starts_with("hc:agent:uhCAk"));
+    assert_eq!(hash_from_uri(&uri).unwrap(), agent);
+  }
+
+  #[test]
+  fn test_target_uri__round_trip() {
+    let targets = [
+      AnyLinkableHash::from(EntryHash::from_raw_36(vec![1; 36])),
+      AnyLinkableHash::from(ActionHash::from_raw_36(vec![2; 36])),
+      AnyLinkableHash::from(ExternalHash::from_raw_36(vec![3; 36])),
+    ];
+    for target in targets {
+      assert_eq!(hash_from_uri(&target_uri(&target)).unwrap(), target);
+    }
+  }
+
+  #[test]
+  fn test_hash_from_uri__external() {
+    let uri = "https://ipfs.io/ipfs/Qme7ss3ARVgxv6rXqVPiikMJ8u2NLgmgszg13pYrDKEoiu";
+    let hash = hash_from_uri(uri).unwrap();
+    assert_eq!(hash, AnyLinkableHash::from(external_hash_for_uri(uri)));
+    assert_eq!(hash, hash_from_uri(uri).unwrap());
+    assert_ne!(hash, hash_from_uri("https://ipfs.io/").unwrap());
+    assert!(target_uri(&hash).starts_with("hc:external:"));
+  }
+
+  #[test]
+  fn test_hash_from_uri__errors() {
+    assert_eq!(
+      hash_from_uri("Nuka Sushi"),
+      Err(UriError::NotAUri("Nuka Sushi".to_string()))
+    );
+    assert_eq!(
+      hash_from_uri("hc:entry:nope"),
+      Err(UriError::InvalidHash("hc:entry:nope".to_string()))
+    );
+  }
+}
@@ -0,0 +1,157 @@
+//! Values on the scale a rater actually used (stars, percentages, thumbs up/down), which
+//! `create_trust_atom` converts to the canonical -1..1 value string, keeping the original in the
+//! trust atom's extra fields under `ORIGINAL_VALUE_EXTRA_KEY`.
+//!
+//! `normalize_value` gives the canonical value string, for the zome and for anything reading
+//! values from elsewhere (eg JSON).
+
+#![allow(clippy::module_name_repetitions)]
+
+use rust_decimal::prelude::*;
+use serde::{Deserialize, Serialize};
+use std::fmt;
+
+/// Extra field holding the JSON of a typed value, eg `{"n":4,"max":5}`, for rendering it back
+pub const ORIGINAL_VALUE_EXTRA_KEY: &str = "original_value";
+
+/// Untagged, so a plain string is still a raw value, and each scale has its own JSON shape:
+/// `"0.8"`, `{"n": 4, "max": 5}`, `{"percent": 80}`, `true`
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq, Eq)]
+#[serde(untagged)]
+pub enum TrustAtomValue {
+  /// Decimal in the range -1..1
+  Raw(String),
+  /// `n` of `max` stars: 1 star is -1, `max` stars is 1
+  Stars { n: u32, max: u32 },
+  /// 0 (-1) to 100 (1) percent; a struct, since a bare number would look like a raw value
+  Percent { percent: u8 },
+  /// Thumbs up (1) or down (-1)
+  Bool(bool),
+}
+
+impl TrustAtomValue {
+  /// JSON of the value as given, to store under `ORIGINAL_VALUE_EXTRA_KEY`; `None` for raw values,
+  /// which are already on the canonical scale
+  #[must_use]
+  pub fn original_value_json(&self) -> Option<String> {
+    match self {
+      Self::Raw(_) => None,
+      typed => serde_json::to_string(typed).ok(),
+    }
+  }
+}
+
+impl From<String> for TrustAtomValue {
+  fn from(value: String) -> Self {
+    Self::Raw(value)
+  }
+}
+
+impl From<&str> for TrustAtomValue {
+  fn from(value: &str) -> Self {
+    Self::Raw(value.to_string())
+  }
+}
+
+/// Canonical form of a value string, as stored in link tags: rounded to 9 significant figures,
+/// without a leading zero (eg `-.800000000`), and with `1` and `-1` as `.999999999` and
+/// `-.999999999`
+pub fn normalize_value(value: &str) -> Result<String, ValueError> {
+  let decimal = Decimal::from_str(value)
+    .map_err(|error| ValueError::NotNumeric(value.to_string(), error.to_string()))?;
+  let decimal = decimal
+    .round_sf_with_strategy(9, RoundingStrategy::MidpointAwayFromZero)
+    .ok_or_else(|| ValueError::Unprocessable(value.to_string()))?;
+  if decimal == Decimal::ONE {
+    Ok(".999999999".to_string())
+  } else if decimal == Decimal::NEGATIVE_ONE {
+    Ok("-.999999999".to_string())
+  } else if decimal > Decimal::NEGATIVE_ONE && decimal < Decimal::ONE {
+    Ok(decimal.to_string().replace("0.", "."))
+  } else {
+    Err(ValueError::OutOfRange(value.to_string()))
+  }
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum ValueError {
+  /// The value as given, and the parse error
+  NotNumeric(String, String),
+  Unprocessable(String),
+  OutOfRange(String),
+}
+
+impl fmt::Display for ValueError {
+  fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+    match self {
+      Self::NotNumeric(value, error) => write!(
+        f,
+        "Value could not be processed: `{value}`.  Error: `{error}`"
+      ),
+      Self::Unprocessable(value) => write!(f, "Value could not be processed: `{value}`"),
+      Self::OutOfRange(value) => {
+        write!(f, "Value must be in the range -1..1, but got: `{value}`")
+      }
+    }
+  }
+}
+
+impl std::error::Error for ValueError {}
+
+#[cfg(test)]
+#[allow(clippy::unwrap_used)]
+#[allow(non_snake_case)]
+mod tests {
+
+  use super::*;
+
+  #[test]
+  fn test_json_shapes() {
+    let values_and_json = [
+      (TrustAtomValue::from("0.8"), r#""0.8""#),
+      (TrustAtomValue::Stars { n: 4, max: 5 }, r#"{"n":4,"max":5}"#),
+      (TrustAtomValue::Percent { percent: 80 }, r#"{"percent":80}"#),
+      (TrustAtomValue::Bool(false), "false"),
+    ];
+
+    for (value, json) in values_and_json {
+      assert_eq!(serde_json::to_string(&value).unwrap(), json);
+      assert_eq!(serde_json::from_str::<TrustAtomValue>(json).unwrap(), value);
+    }
+  }
+
+  #[test]
+  fn test_original_value_json() {
+    assert_eq!(TrustAtomValue::from("0.8").original_value_json(), None);
+    let stars = TrustAtomValue::Stars { n: 4, max: 5 };
+    let json = stars.original_value_json().unwrap();
+    assert_eq!(
+      serde_json::from_str::<TrustAtomValue>(&json).unwrap(),
+      stars
+    );
+  }
+
+  #[test]
+  fn test_normalize_value() {
+    let input_and_expected = [
+      ("0.8", ".800000000"),
+      ("-0.8", "-.800000000"),
+      ("1", ".999999999"),
+      ("-1.0", "-.999999999"),
+      ("0", "0"),
+      ("0.0000000001", ".000000000100000000"),
+    ];
+    for (input, expected) in input_and_expected {
+      assert_eq!(normalize_value(input).unwrap(), expected, "{input}");
+    }
+
+    assert_eq!(
+      normalize_value("1.1"),
+      Err(ValueError::OutOfRange("1.1".to_string()))
+    );
+    assert!(matches!(
+      normalize_value("lots"),
+      Err(ValueError::NotNumeric(_, _))
+    ));
+  }
+}